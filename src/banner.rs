@@ -0,0 +1,22 @@
+//! Notices injected at the top of a chapter.
+
+/// An html block shown above the chapter content.
+pub(crate) fn banner(kind: &str, message: &str) -> String {
+    format!(
+        "<div class=\"fm-banner fm-banner-{}\" role=\"note\">{}</div>\n\n",
+        kind, message
+    )
+}
+
+/// A callout in the chapter's markdown: an mdbook-admonish block when that
+/// preprocessor is configured, a blockquote otherwise.
+pub(crate) fn callout(kind: &str, text: &str, admonish: bool) -> String {
+    if admonish {
+        return format!("```admonish {}\n{}\n```\n\n", kind, text);
+    }
+    let mut label = kind.to_string();
+    if let Some(first) = label.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    format!("> **{}:** {}\n\n", label, text.replace('\n', "\n> "))
+}
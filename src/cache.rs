@@ -0,0 +1,69 @@
+//! Chapters rendered by an earlier build, so `mdbook serve` renders again
+//! only the chapters whose inputs changed.
+//!
+//! A chapter's key hashes everything rendering it reads: its content, name,
+//! path, defaults and part, and for the whole book the preprocessor version,
+//! `book.toml`, the renderer, every chapter's keys (series, related chapters
+//! and tag links look across chapters) and the day (for dates and banners).
+use crate::Rendered;
+use log::debug;
+use mdbook::errors::Error;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+
+/// Rendered chapters by key.
+#[derive(Default)]
+pub(crate) struct Cache {
+    /// Where the cache is kept, `None` when caching is off.
+    path: Option<PathBuf>,
+    chapters: HashMap<String, Rendered>,
+}
+
+impl Cache {
+    /// The cache kept at `path`. It starts empty when there is none yet, or
+    /// it can't be read, e.g. after an upgrade.
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let chapters = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            chapters,
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// The chapter rendered under `key` before.
+    pub(crate) fn get(&self, key: &str) -> Option<Rendered> {
+        self.chapters.get(key).cloned()
+    }
+
+    /// Replace the cache with the chapters of this build, dropping the rest.
+    pub(crate) fn save(&mut self, chapters: HashMap<String, Rendered>) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let hits = chapters
+            .keys()
+            .filter(|key| self.chapters.contains_key(*key))
+            .count();
+        debug!("{} of {} chapters from the cache", hits, chapters.len());
+        self.chapters = chapters;
+        let json = serde_json::to_string(&self.chapters)?;
+        fs::write(path, json)
+            .map_err(|e| Error::msg(format!("unable to write {}: {}", path.display(), e)))
+    }
+}
+
+/// A key for everything in `inputs`.
+pub(crate) fn key(inputs: impl Hash) -> String {
+    let mut hasher = DefaultHasher::new();
+    inputs.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
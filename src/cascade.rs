@@ -0,0 +1,264 @@
+//! Keys chapters inherit instead of writing them out.
+//!
+//! A chapter's own keys win over those from its file's git history (with
+//! `git_updated`), then its parent chapters' (with `inherit_parent`), then a
+//! `_frontmatter.toml` in its directory, then one in a parent directory, then
+//! the book-wide `[preprocessor.frontmatter.defaults]`. The `_frontmatter.toml`
+//! files are in `src`, or in a tree laid out like it under `defaults_dir`.
+//!
+//! Parts can't hold frontmatter, so their keys come from `_parts.toml` next
+//! to `book.toml`, one table per part title. With `part_cascade` they rank
+//! just below parent chapters.
+use crate::config::Config;
+use crate::git;
+use crate::parse;
+use crate::value;
+use mdbook::book::{Book, Chapter};
+use mdbook::errors::Error;
+use mdbook::BookItem;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory defaults file.
+pub(crate) const DIRECTORY_FILE: &str = "_frontmatter.toml";
+
+/// Name of the file holding keys for parts.
+pub(crate) const PARTS_FILE: &str = "_parts.toml";
+
+/// Defaults for every chapter of a book.
+pub(crate) struct Cascade {
+    book: Vec<(String, String)>,
+    /// Keys from the git history of each chapter, by chapter source path.
+    git: HashMap<PathBuf, Vec<(String, String)>>,
+    /// Keys from each `_frontmatter.toml`, by directory relative to `src`.
+    directories: HashMap<PathBuf, Vec<(String, String)>>,
+    /// Keys inherited from parent chapters, by chapter source path.
+    parents: HashMap<PathBuf, Vec<(String, String)>>,
+    /// Keys from `_parts.toml`, by part title.
+    parts: HashMap<String, Vec<(String, String)>>,
+    /// The part each chapter is in, by chapter source path.
+    chapter_parts: HashMap<PathBuf, String>,
+    part_cascade: bool,
+}
+
+impl Cascade {
+    /// Read the book defaults, `_parts.toml` and the `_frontmatter.toml` files
+    /// of every directory holding a chapter.
+    pub(crate) fn new(
+        config: &Config,
+        book: &Book,
+        root: &Path,
+        src_dir: &Path,
+    ) -> Result<Self, Error> {
+        let defaults_dir = match &config.defaults_dir {
+            Some(dir) => root.join(dir),
+            None => src_dir.to_path_buf(),
+        };
+        let mut directories = HashMap::new();
+        for item in book.iter() {
+            let BookItem::Chapter(Chapter {
+                source_path: Some(source_path),
+                ..
+            }) = item
+            else {
+                continue;
+            };
+            for dir in source_path.ancestors().skip(1) {
+                if directories.contains_key(dir) {
+                    continue;
+                }
+                let keys = read_directory_file(&defaults_dir.join(dir).join(DIRECTORY_FILE))?;
+                directories.insert(dir.to_path_buf(), keys);
+            }
+        }
+
+        let mut parents = HashMap::new();
+        if config.inherit_parent {
+            inherit(&book.sections, &[], config, &mut parents);
+        }
+
+        let parts = read_parts_file(&root.join(PARTS_FILE))?;
+        let mut chapter_parts = HashMap::new();
+        let mut part = None;
+        for item in &book.sections {
+            match item {
+                BookItem::PartTitle(title) => part = Some(title.clone()),
+                BookItem::Chapter(_) => {
+                    if let Some(title) = &part {
+                        record_part(item, title, &mut chapter_parts);
+                    }
+                }
+                BookItem::Separator => (),
+            }
+        }
+
+        Ok(Self {
+            book: config
+                .defaults
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            git: git::history(config, book, src_dir),
+            directories,
+            parents,
+            parts,
+            chapter_parts,
+            part_cascade: config.part_cascade,
+        })
+    }
+
+    /// Defaults for a chapter, nearest first.
+    pub(crate) fn defaults_for(&self, chapter: &Chapter) -> Vec<(String, String)> {
+        let mut defaults = vec![];
+        for keys in [&self.git, &self.parents] {
+            if let Some(keys) = chapter.source_path.as_ref().and_then(|path| keys.get(path)) {
+                merge(&mut defaults, keys);
+            }
+        }
+        if let (true, Some((_, keys))) = (self.part_cascade, self.part_of(chapter)) {
+            merge(&mut defaults, keys);
+        }
+        let directories = chapter
+            .source_path
+            .iter()
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter_map(|dir| self.directories.get(dir));
+        for keys in directories.chain([&self.book]) {
+            merge(&mut defaults, keys);
+        }
+        defaults
+    }
+
+    /// The part a chapter is in, and the part's keys.
+    pub(crate) fn part_of(&self, chapter: &Chapter) -> Option<(&str, &[(String, String)])> {
+        let title = self.chapter_parts.get(chapter.source_path.as_ref()?)?;
+        let keys = self.parts.get(title)?;
+        Some((title, keys))
+    }
+}
+
+/// The first chapter of every part, which shows the part's overview.
+pub(crate) fn part_heads(items: &[BookItem]) -> Vec<PathBuf> {
+    let mut heads = vec![];
+    let mut in_new_part = false;
+    for item in items {
+        match item {
+            BookItem::PartTitle(_) => in_new_part = true,
+            // draft entries have no page to show it on
+            BookItem::Chapter(chapter) if in_new_part && chapter.source_path.is_some() => {
+                heads.extend(chapter.source_path.clone());
+                in_new_part = false;
+            }
+            _ => (),
+        }
+    }
+    heads
+}
+
+fn record_part(item: &BookItem, title: &str, chapter_parts: &mut HashMap<PathBuf, String>) {
+    if let BookItem::Chapter(chapter) = item {
+        if let Some(source_path) = &chapter.source_path {
+            chapter_parts.insert(source_path.clone(), title.to_string());
+        }
+        for item in &chapter.sub_items {
+            record_part(item, title, chapter_parts);
+        }
+    }
+}
+
+/// Append every default the chapter doesn't set itself, returning those keys.
+pub(crate) fn merge(
+    frontmatter: &mut Vec<(String, String)>,
+    defaults: &[(String, String)],
+) -> Vec<(String, String)> {
+    let missing: Vec<_> = defaults
+        .iter()
+        .filter(|(key, _)| !frontmatter.iter().any(|(k, _)| k == key))
+        .cloned()
+        .collect();
+    frontmatter.extend(missing.iter().cloned());
+    missing
+}
+
+/// Record what each chapter under `items` inherits from `inherited` and its
+/// parent chapters.
+fn inherit(
+    items: &[BookItem],
+    inherited: &[(String, String)],
+    config: &Config,
+    parents: &mut HashMap<PathBuf, Vec<(String, String)>>,
+) {
+    for item in items {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+        if let Some(source_path) = &chapter.source_path {
+            parents.insert(source_path.clone(), inherited.to_vec());
+        }
+
+        let mut keys: Vec<_> = parse::read_frontmatter(&chapter.content, config)
+            .into_iter()
+            .filter(|(key, _)| !config.inherit_skip_keys.contains(key))
+            .collect();
+        merge(&mut keys, inherited);
+        inherit(&chapter.sub_items, &keys, config, parents);
+    }
+}
+
+/// Keys from a `_frontmatter.toml`, or none if there isn't one.
+fn read_directory_file(path: &Path) -> Result<Vec<(String, String)>, Error> {
+    let table: BTreeMap<String, toml::Value> = read_toml(path)?.unwrap_or_default();
+    Ok(to_frontmatter(table))
+}
+
+/// Keys per part title from `_parts.toml`, or none if there isn't one.
+fn read_parts_file(path: &Path) -> Result<HashMap<String, Vec<(String, String)>>, Error> {
+    let parts: BTreeMap<String, BTreeMap<String, toml::Value>> =
+        read_toml(path)?.unwrap_or_default();
+    Ok(parts
+        .into_iter()
+        .map(|(title, table)| (title, to_frontmatter(table)))
+        .collect())
+}
+
+fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, Error> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    toml::from_str(&text)
+        .map(Some)
+        .map_err(|e| Error::msg(format!("invalid {}: {}", path.display(), e)))
+}
+
+fn to_frontmatter(table: BTreeMap<String, toml::Value>) -> Vec<(String, String)> {
+    table
+        .into_iter()
+        .map(|(key, value)| (key, value::from_toml(value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn part_heads_skip_drafts() {
+        let items = vec![
+            BookItem::Chapter(Chapter::new("Preface", String::new(), "preface.md", vec![])),
+            BookItem::PartTitle("Guide".to_string()),
+            BookItem::Chapter(Chapter::new_draft("Draft", vec![])),
+            BookItem::Chapter(Chapter::new("Setup", String::new(), "setup.md", vec![])),
+            BookItem::Chapter(Chapter::new("Usage", String::new(), "usage.md", vec![])),
+            BookItem::Separator,
+            BookItem::Chapter(Chapter::new(
+                "Appendix",
+                String::new(),
+                "appendix.md",
+                vec![],
+            )),
+        ];
+        assert_eq!(part_heads(&items), [PathBuf::from("setup.md")]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Every chapter's frontmatter, collected before any chapter is rendered, for
+//! features that look across chapters.
+use crate::value::split_list;
+use mdbook::book::{Book, Chapter};
+use mdbook::BookItem;
+use std::path::{Path, PathBuf};
+
+/// A chapter as the rest of the book sees it.
+#[derive(Hash)]
+pub(crate) struct Entry {
+    pub(crate) path: PathBuf,
+    /// The `title` key, or the SUMMARY.md text.
+    pub(crate) title: String,
+    /// Title of the part the chapter is in.
+    pub(crate) part: Option<String>,
+    /// Keys as the chapter ends up with them, defaults included.
+    pub(crate) frontmatter: Vec<(String, String)>,
+}
+
+impl Entry {
+    /// The first value for `key`.
+    pub(crate) fn value(&self, key: &str) -> Option<&str> {
+        self.frontmatter
+            .iter()
+            .find(|(k, v)| k == key && !v.is_empty())
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The items of a list value like `tags`.
+    pub(crate) fn list(&self, key: &str) -> Vec<String> {
+        self.value(key).map(split_list).unwrap_or_default()
+    }
+}
+
+/// Every chapter with a page, in book order.
+pub(crate) struct Catalog {
+    pub(crate) entries: Vec<Entry>,
+}
+
+impl Catalog {
+    pub(crate) fn new(
+        book: &Book,
+        frontmatter: impl Fn(&Chapter) -> Vec<(String, String)>,
+    ) -> Self {
+        let mut entries = vec![];
+        let mut part = None;
+        for item in book.iter() {
+            let chapter = match item {
+                BookItem::Chapter(chapter) => chapter,
+                BookItem::PartTitle(title) => {
+                    part = Some(title.clone());
+                    continue;
+                }
+                BookItem::Separator => continue,
+            };
+            let Some(path) = chapter.path.clone() else {
+                continue;
+            };
+            let frontmatter = frontmatter(chapter);
+            let title = frontmatter
+                .iter()
+                .find(|(key, value)| key == "title" && !value.is_empty())
+                .map_or_else(|| chapter.name.clone(), |(_, title)| title.clone());
+            entries.push(Entry {
+                path,
+                title,
+                part: part.clone(),
+                frontmatter,
+            });
+        }
+        Self { entries }
+    }
+
+    /// The title of the chapter at `path`.
+    pub(crate) fn title(&self, path: &Path) -> &str {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .map_or("", |entry| entry.title.as_str())
+    }
+
+    /// The entry for a chapter.
+    pub(crate) fn get(&self, chapter: &Chapter) -> Option<&Entry> {
+        let path = chapter.path.as_ref()?;
+        self.entries.iter().find(|entry| &entry.path == path)
+    }
+}
+
+/// An entry for tests, titled by its `title` key or its file name.
+#[cfg(test)]
+pub(crate) fn entry(path: &str, keys: &[(&str, &str)]) -> Entry {
+    let frontmatter: Vec<_> = keys
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let title = keys
+        .iter()
+        .find(|(key, _)| *key == "title")
+        .map_or(path.trim_end_matches(".md"), |(_, title)| title);
+    Entry {
+        path: PathBuf::from(path),
+        title: title.to_string(),
+        part: None,
+        frontmatter,
+    }
+}
@@ -0,0 +1,69 @@
+//! Rows computed from the chapter body rather than written in frontmatter.
+//!
+//! Each computed field is a name and a function of the chapter's [Stats];
+//! adding one is adding an entry to [FIELDS].
+use crate::config::Config;
+use crate::parse;
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// What's counted in a chapter body.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    /// Words of prose, leaving out frontmatter blocks and code blocks.
+    pub(crate) words: usize,
+    pub(crate) code_blocks: usize,
+    pub(crate) images: usize,
+}
+
+type Compute = fn(&Stats, &Config) -> String;
+
+/// Every computed field by name, the row key it's shown as.
+pub(crate) const FIELDS: &[(&str, Compute)] = &[
+    ("words", |stats, _| stats.words.to_string()),
+    ("reading time", |stats, config| {
+        let minutes = stats.words.div_ceil(config.words_per_minute.max(1));
+        format!("{} min", minutes.max(1))
+    }),
+    ("code blocks", |stats, _| stats.code_blocks.to_string()),
+    ("images", |stats, _| stats.images.to_string()),
+];
+
+/// The configured computed rows for a chapter, from its content after
+/// conditionals.
+pub(crate) fn fields(content: &str, config: &Config) -> Vec<(String, String)> {
+    if config.computed.is_empty() {
+        return vec![];
+    }
+    let stats = Stats::new(content, config);
+    config
+        .computed
+        .iter()
+        .filter_map(|name| {
+            let (_, compute) = FIELDS.iter().find(|(field, _)| field == name)?;
+            Some((name.clone(), compute(&stats, config)))
+        })
+        .collect()
+}
+
+impl Stats {
+    fn new(content: &str, config: &Config) -> Self {
+        let mut stats = Self::default();
+        let mut in_code_block = false;
+        let content = parse::splice_frontmatter(content, config, |_, _| String::new());
+        for event in pulldown_cmark::Parser::new(&content) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => {
+                    in_code_block = true;
+                    stats.code_blocks += 1;
+                }
+                Event::End(TagEnd::CodeBlock) => in_code_block = false,
+                Event::Start(Tag::Image { .. }) => stats.images += 1,
+                Event::Text(text) | Event::Code(text) if !in_code_block => {
+                    stats.words += text.split_whitespace().count();
+                }
+                _ => (),
+            }
+        }
+        stats
+    }
+}
@@ -0,0 +1,730 @@
+//! Configuration read from the `[preprocessor.frontmatter]` table in `book.toml`.
+use crate::computed;
+use crate::date;
+use crate::gettext::GettextMode;
+use crate::plain;
+use crate::profile::Profile;
+use crate::value;
+use mdbook::errors::Error;
+use mdbook::preprocess::PreprocessorContext;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Options for the frontmatter preprocessor.
+///
+/// Every field has a default, so the table itself is optional.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Keep `draft: true` chapters. Overridden by
+    /// `MDBOOK_FRONTMATTER_DRAFTS=include|exclude`.
+    pub include_drafts: bool,
+    /// Preview build: keep chapters whose `publish_date` is in the future,
+    /// with an "unpublished" banner. Overridden by
+    /// `MDBOOK_FRONTMATTER_PREVIEW=true|false`.
+    pub preview: bool,
+    /// What happens to a chapter once its `expires` or `review_by` date has
+    /// passed.
+    pub expired: ExpiredAction,
+    /// Start `deprecated: true` and `status: Deprecated` chapters with a
+    /// banner.
+    pub deprecation_banner: bool,
+    /// Message of the deprecation banner, where `{{fm.key}}` and
+    /// `{{#if fm.key}}` work as in chapters.
+    pub deprecation_message: String,
+    /// Keys whose value is shown as a callout at the top of the chapter, and
+    /// the kind of admonition each becomes. None by default.
+    pub callouts: BTreeMap<String, String>,
+    /// Audience this build is for. Chapters with an `audience` key are only
+    /// kept when it lists this audience (or "all"). Overridden by
+    /// `MDBOOK_FRONTMATTER_AUDIENCE`.
+    pub audience: String,
+    /// Use the `title` key as the chapter's name instead of the SUMMARY.md text.
+    pub override_title: bool,
+    /// Write per-chapter `<head>` tags into the theme's `head.hbs`.
+    pub head_tags: bool,
+    /// Add Open Graph tags to the head tags.
+    pub open_graph: bool,
+    /// Add Twitter/X card tags to the head tags.
+    pub twitter_cards: bool,
+    /// The site's Twitter/X account for `twitter:site`, e.g. "@acme".
+    pub twitter_site: Option<String>,
+    /// Add schema.org Article JSON-LD to the head tags.
+    pub json_ld: bool,
+    /// Absolute URL the book is published at. Falls back to
+    /// `output.html.site-url` when that is absolute.
+    pub base_url: Option<String>,
+    /// Built-in preset for key order, labels and date formatting.
+    pub profile: Profile,
+    /// Give the table a caption, a body and row headers, for screen readers
+    /// and accessibility audits.
+    pub accessible_table: bool,
+    /// Caption of the table with `accessible_table`.
+    pub table_caption: String,
+    /// Add a "#" link to each row's `fm-<key>` id.
+    pub row_anchors: bool,
+    /// Leave out rows of keys without a value, like `reviewer:`.
+    pub hide_empty: bool,
+    /// Keys rendered first, in this order. Overrides the profile's order.
+    pub key_order: Vec<String>,
+    /// Table header text per key. Overrides the profile's labels.
+    pub labels: HashMap<String, String>,
+    /// Language for labels and month names. Defaults to `book.language`.
+    pub language: Option<String>,
+    /// Table header text per language and key, e.g. `[i18n.fr] author = "Auteur"`.
+    pub i18n: HashMap<String, HashMap<String, String>>,
+    /// How frontmatter blocks are presented to `mdbook-xgettext`.
+    pub gettext: GettextMode,
+    /// For the `markdown` renderer, turn each block into a plain
+    /// `- **key:** value` list instead of removing it.
+    pub markdown_metadata: bool,
+    /// What each renderer gets in place of a frontmatter block, overriding
+    /// the built-in choice for it.
+    pub renderer_output: BTreeMap<String, RendererOutput>,
+    /// Render the `status` value as a colored badge.
+    pub status_badges: bool,
+    /// Badge color per status value (case-insensitive).
+    pub status_colors: HashMap<String, String>,
+    /// Keys holding dates, in addition to the profile's date keys.
+    pub date_keys: Vec<String>,
+    /// `strftime` format for date values. Dates are shown as written when
+    /// unset, except for profile date keys.
+    pub date_format: Option<String>,
+    /// Show dates as written/formatted, or relative to the build time.
+    pub date_display: DateDisplay,
+    /// Extra linkification rules applied after the built-in ones.
+    pub linkify: Vec<LinkifyRule>,
+    /// Link `(@username)` handles to GitHub profiles.
+    pub linkify_github: bool,
+    /// Base URL for GitHub profile links, e.g. a GitHub Enterprise host.
+    pub github_base_url: String,
+    /// Show the GitHub avatar next to linked `(@username)` handles.
+    pub github_avatars: bool,
+    /// Link `(~username)` handles to GitLab profiles.
+    pub linkify_gitlab: bool,
+    /// Base URL for GitLab profile links, e.g. a self-hosted instance.
+    pub gitlab_base_url: String,
+    /// Link `@user@instance.tld` handles to Fediverse profiles.
+    pub linkify_fediverse: bool,
+    /// Render ORCID iDs in `author` values as ORCID badge links.
+    pub linkify_orcid: bool,
+    /// Keys whose DOI values link to `https://doi.org/`. Empty disables it.
+    pub doi_keys: Vec<String>,
+    /// Repository for `#123` references. Falls back to
+    /// `output.html.git-repository-url`.
+    pub repository_url: Option<String>,
+    /// Keys whose `#123` references link to the repository's issues.
+    pub issue_keys: Vec<String>,
+    /// Turn bare `http(s)://` URLs in any value into links.
+    pub autolink_urls: bool,
+    /// Keys whose values are never auto-linked.
+    pub autolink_skip_keys: Vec<String>,
+    /// How `mailto:` links are written to keep addresses away from harvesters.
+    pub email_obfuscation: EmailObfuscation,
+    /// Other spellings of keys, by the key they stand for, e.g.
+    /// `author = ["Author", "by"]`. Chapters' keys are renamed before anything
+    /// else sees them.
+    pub key_aliases: BTreeMap<String, Vec<String>>,
+    /// Keys every chapter must have (its own or from defaults).
+    pub required_keys: Vec<String>,
+    /// Report date keys that don't parse as dates, email keys that aren't an
+    /// address and URL keys that aren't an absolute URL.
+    pub validate_types: bool,
+    /// Keys chapters may use, with `deny_unknown_keys`.
+    pub allowed_keys: Vec<String>,
+    /// Report keys that are neither in `allowed_keys` nor in the schema's
+    /// `properties`. A schema with `additionalProperties: false` does the
+    /// same.
+    pub deny_unknown_keys: bool,
+    /// JSON Schema file (relative to the book root) every chapter's keys are
+    /// checked against.
+    pub schema: Option<String>,
+    /// Whether frontmatter problems are warnings or fail the build.
+    /// Overridden by `MDBOOK_FRONTMATTER_MODE`.
+    pub mode: Mode,
+    /// What happens when a chapter can't be rendered: fail the build, or
+    /// warn and pass the chapter on as written.
+    pub on_error: OnError,
+    /// Add how many chapters use each key to the summary logged at the end
+    /// of a run. Overridden by `MDBOOK_FRONTMATTER_VERBOSE`.
+    pub verbose: bool,
+    /// Print a diff of what the preprocessor would change in each chapter
+    /// and pass the book on untouched, writing no files. Overridden by
+    /// `MDBOOK_FRONTMATTER_DRY_RUN`.
+    pub dry_run: bool,
+    /// Keep each rendered chapter in `cache_path`, and reuse it while
+    /// nothing it's rendered from changes.
+    pub cache: bool,
+    /// Path of the cache, relative to the book root.
+    pub cache_path: String,
+    /// Which value a key written twice in a block keeps.
+    pub duplicate_keys: DuplicateKeys,
+    /// Read `+++` blocks anywhere in a chapter, not just one at its very top.
+    pub anywhere: bool,
+    /// Render `+++` blocks below the top one as tables of their section's own
+    /// keys, kept apart from the chapter's frontmatter.
+    pub sections: bool,
+    /// Keys holding several comma/`and` separated (or `[..]` array) items,
+    /// rendered as a list.
+    pub list_keys: Vec<String>,
+    /// Keys every chapter gets unless it sets them itself.
+    #[serde(deserialize_with = "scalar_map")]
+    pub defaults: BTreeMap<String, String>,
+    /// Directory holding the `_frontmatter.toml` files, laid out like `src`
+    /// and relative to the book root. `src` itself when unset.
+    pub defaults_dir: Option<String>,
+    /// Give chapters without frontmatter a table of the defaults.
+    pub default_table: bool,
+    /// Rows computed from the chapter body, by name: `words`,
+    /// `reading time`, `code blocks` and `images`.
+    pub computed: Vec<String>,
+    /// Add a `reading time` row, the same as listing it in `computed`.
+    pub reading_time: bool,
+    /// Reading speed for `reading time`.
+    pub words_per_minute: usize,
+    /// Chapters without an `updated` key get the date of their source file's
+    /// last commit.
+    pub git_updated: bool,
+    /// Add a `contributors` row of everyone who committed to the chapter's
+    /// source file, other than its declared authors.
+    pub git_contributors: bool,
+    /// Sub-chapters inherit the keys of their parent chapter.
+    pub inherit_parent: bool,
+    /// Keys describing a single page, which sub-chapters never inherit.
+    pub inherit_skip_keys: Vec<String>,
+    /// Chapters get the keys of their part from `_parts.toml` as defaults.
+    pub part_cascade: bool,
+    /// Parts whose chapters are sorted by frontmatter instead of SUMMARY.md
+    /// order, by part title.
+    pub sort_parts: HashMap<String, ChapterOrder>,
+    /// Order chapters among their siblings by their `weight` (or `order`)
+    /// key, and number them to match.
+    pub weights: bool,
+    /// End each chapter with links to the chapters sharing most of its `tags`.
+    pub related: bool,
+    /// How many related chapters to link at most.
+    pub related_limit: usize,
+    /// Add the values of `search_terms_keys` to the search index of the html
+    /// book, in a hidden block.
+    pub search_terms: bool,
+    /// Keys whose values the search index gets.
+    pub search_terms_keys: Vec<String>,
+    /// Keys whose values the whole chapter is wrapped with, as
+    /// `fm-<key>-<value>` classes and `data-<key>` attributes.
+    pub wrapper_keys: Vec<String>,
+    /// Start each chapter with an html comment holding its keys as JSON, for
+    /// preprocessors running after this one.
+    pub metadata_comment: bool,
+    /// End each chapter with a link to its source in the forge.
+    pub edit_link: bool,
+    /// Edit URL with a `{path}` placeholder for the chapter's source path
+    /// relative to the book root. Falls back to
+    /// `output.html.edit-url-template`, then to the file in `repository_url`.
+    pub edit_url_template: Option<String>,
+    /// Text of the edit link.
+    pub edit_link_text: String,
+    /// Add a page per tag listing its chapters, and link the `tags` values to
+    /// them.
+    pub tag_pages: bool,
+    /// Directory of the tag pages in the built book.
+    pub tags_dir: String,
+    /// Name of the chapter listing the tags.
+    pub tags_title: String,
+    /// Show the tags page as a cloud, each tag sized by how often it's used.
+    pub tag_cloud: bool,
+    /// Add a page listing every author and their chapters, and link author
+    /// names to it.
+    pub author_page: bool,
+    /// Path of the authors page in the built book.
+    pub authors_path: String,
+    /// Name of the authors page.
+    pub authors_title: String,
+    /// Add a page per `category` (e.g. `networking/tls`) and a breadcrumb
+    /// trail to each categorized chapter.
+    pub category_pages: bool,
+    /// Directory of the category pages in the built book.
+    pub categories_dir: String,
+    /// Name of the top-level categories page.
+    pub categories_title: String,
+    /// Add a chapter with a sortable table of every chapter's metadata.
+    pub document_index: bool,
+    /// Path of the document index in the built book.
+    pub document_index_path: String,
+    /// Name of the document index chapter.
+    pub document_index_title: String,
+    /// Keys shown as columns of the document index. `title` links to the
+    /// chapter.
+    pub document_index_columns: Vec<String>,
+    /// One document index table per part.
+    pub document_index_by_part: bool,
+    /// Add a "What's new" chapter from the `date` and `changelog` (or
+    /// `summary`) keys of every chapter.
+    pub changelog: bool,
+    /// Path of the changelog in the built book.
+    pub changelog_path: String,
+    /// Name of the changelog chapter.
+    pub changelog_title: String,
+    /// Show only this many of the newest changes.
+    pub changelog_limit: Option<usize>,
+    /// Write an RSS feed of the chapters with a `date`. Needs `base_url`.
+    pub rss: bool,
+    /// Path of the RSS feed in the built book.
+    pub rss_path: String,
+    /// Write an Atom feed of the chapters with a `date`. Needs `base_url`.
+    pub atom: bool,
+    /// Path of the Atom feed in the built book.
+    pub atom_path: String,
+    /// Write a `sitemap.xml` of every chapter. Needs `base_url`.
+    pub sitemap: bool,
+    /// Path of the sitemap in the built book.
+    pub sitemap_path: String,
+    /// Write every chapter's frontmatter to a JSON file.
+    pub metadata_json: bool,
+    /// Path of the JSON file in the built book.
+    pub metadata_json_path: String,
+    /// Write each chapter's frontmatter to `<chapter>.frontmatter.json` next
+    /// to it.
+    pub chapter_json: bool,
+    /// Write every chapter's frontmatter to a CSV file.
+    pub csv_export: bool,
+    /// Path of the CSV file in the built book. A `.tsv` path is tab separated.
+    pub csv_path: String,
+    /// How many of the newest chapters the feeds list.
+    pub feed_limit: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            include_drafts: false,
+            preview: false,
+            expired: ExpiredAction::Banner,
+            deprecation_banner: false,
+            deprecation_message: "This chapter is deprecated.\
+                {{#if fm.superseded-by}} Use {{fm.superseded-by}} instead.{{/if}}"
+                .to_string(),
+            callouts: BTreeMap::new(),
+            audience: "public".to_string(),
+            override_title: false,
+            head_tags: false,
+            open_graph: true,
+            twitter_cards: true,
+            twitter_site: None,
+            json_ld: true,
+            base_url: None,
+            profile: Profile::Default,
+            accessible_table: false,
+            table_caption: "Chapter metadata".to_string(),
+            row_anchors: false,
+            hide_empty: false,
+            key_order: vec![],
+            labels: HashMap::new(),
+            language: None,
+            i18n: HashMap::new(),
+            gettext: GettextMode::Comment,
+            markdown_metadata: false,
+            renderer_output: BTreeMap::new(),
+            status_badges: false,
+            status_colors: HashMap::new(),
+            date_keys: vec![
+                "date".to_string(),
+                "created".to_string(),
+                "updated".to_string(),
+            ],
+            date_format: None,
+            date_display: DateDisplay::Absolute,
+            linkify: vec![],
+            linkify_github: true,
+            github_base_url: "https://github.com".to_string(),
+            github_avatars: false,
+            linkify_gitlab: false,
+            gitlab_base_url: "https://gitlab.com".to_string(),
+            linkify_fediverse: false,
+            linkify_orcid: false,
+            doi_keys: vec!["doi".to_string(), "reference".to_string()],
+            repository_url: None,
+            issue_keys: vec!["tracking-issue".to_string(), "issue".to_string()],
+            autolink_urls: false,
+            autolink_skip_keys: vec![],
+            email_obfuscation: EmailObfuscation::None,
+            key_aliases: BTreeMap::new(),
+            required_keys: vec![],
+            validate_types: false,
+            allowed_keys: vec![],
+            deny_unknown_keys: false,
+            schema: None,
+            mode: Mode::Lenient,
+            on_error: OnError::Fail,
+            verbose: false,
+            dry_run: false,
+            cache: false,
+            cache_path: ".frontmatter-cache.json".to_string(),
+            duplicate_keys: DuplicateKeys::First,
+            anywhere: false,
+            sections: false,
+            list_keys: vec![
+                "author".to_string(),
+                "authors".to_string(),
+                "contributors".to_string(),
+            ],
+            defaults: BTreeMap::new(),
+            defaults_dir: None,
+            default_table: false,
+            computed: vec![],
+            reading_time: false,
+            words_per_minute: 200,
+            git_updated: false,
+            git_contributors: false,
+            inherit_parent: false,
+            inherit_skip_keys: vec![
+                "title".to_string(),
+                "description".to_string(),
+                "aliases".to_string(),
+                "canonical".to_string(),
+                "image".to_string(),
+                "weight".to_string(),
+                "order".to_string(),
+            ],
+            part_cascade: false,
+            sort_parts: HashMap::new(),
+            weights: false,
+            related: false,
+            related_limit: 5,
+            search_terms: false,
+            search_terms_keys: vec!["keywords".to_string(), "tags".to_string()],
+            wrapper_keys: vec![],
+            metadata_comment: false,
+            edit_link: false,
+            edit_url_template: None,
+            edit_link_text: "Edit this page".to_string(),
+            tag_pages: false,
+            tags_dir: "tags".to_string(),
+            tags_title: "Tags".to_string(),
+            tag_cloud: false,
+            author_page: false,
+            authors_path: "authors.md".to_string(),
+            authors_title: "Authors".to_string(),
+            category_pages: false,
+            categories_dir: "categories".to_string(),
+            categories_title: "Categories".to_string(),
+            document_index: false,
+            document_index_path: "document-index.md".to_string(),
+            document_index_title: "Document index".to_string(),
+            document_index_columns: vec![
+                "title".to_string(),
+                "author".to_string(),
+                "status".to_string(),
+                "date".to_string(),
+            ],
+            document_index_by_part: false,
+            changelog: false,
+            changelog_path: "changelog.md".to_string(),
+            changelog_title: "What's new".to_string(),
+            changelog_limit: None,
+            rss: false,
+            rss_path: "rss.xml".to_string(),
+            atom: false,
+            atom_path: "atom.xml".to_string(),
+            feed_limit: 20,
+            metadata_json: false,
+            metadata_json_path: "frontmatter.json".to_string(),
+            chapter_json: false,
+            csv_export: false,
+            csv_path: "frontmatter.csv".to_string(),
+            sitemap: false,
+            sitemap_path: "sitemap.xml".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Read the `preprocessor.<name>` table and fill in book-wide fallbacks.
+    pub fn from_context(ctx: &PreprocessorContext, name: &str) -> Result<Self, Error> {
+        Self::from_book_config(&ctx.config, name)
+    }
+
+    /// Same as [Config::from_context], from a `book.toml` loaded outside of a
+    /// build.
+    pub fn from_book_config(book_config: &mdbook::Config, name: &str) -> Result<Self, Error> {
+        let mut config: Config = book_config
+            .get_deserialized_opt(format!("preprocessor.{}", name))?
+            .unwrap_or_default();
+
+        match std::env::var("MDBOOK_FRONTMATTER_DRAFTS").as_deref() {
+            Ok("include") => config.include_drafts = true,
+            Ok("exclude") => config.include_drafts = false,
+            _ => (),
+        }
+
+        if let Ok(preview) = std::env::var("MDBOOK_FRONTMATTER_PREVIEW") {
+            config.preview = value::is_true(&preview);
+        }
+
+        match std::env::var("MDBOOK_FRONTMATTER_MODE").as_deref() {
+            Ok("strict") => config.mode = Mode::Strict,
+            Ok("lenient") => config.mode = Mode::Lenient,
+            Ok(other) => {
+                return Err(Error::msg(format!(
+                    "MDBOOK_FRONTMATTER_MODE must be \"strict\" or \"lenient\", not {:?}",
+                    other
+                )))
+            }
+            Err(_) => (),
+        }
+
+        if let Ok(verbose) = std::env::var("MDBOOK_FRONTMATTER_VERBOSE") {
+            config.verbose = value::is_true(&verbose);
+        }
+
+        if let Ok(dry_run) = std::env::var("MDBOOK_FRONTMATTER_DRY_RUN") {
+            config.dry_run = value::is_true(&dry_run);
+        }
+
+        if let Ok(audience) = std::env::var("MDBOOK_FRONTMATTER_AUDIENCE") {
+            config.audience = audience;
+        }
+
+        if config.reading_time && !config.computed.iter().any(|name| name == "reading time") {
+            config.computed.push("reading time".to_string());
+        }
+        if let Some(name) = config
+            .computed
+            .iter()
+            .find(|name| !computed::FIELDS.iter().any(|(field, _)| field == name))
+        {
+            return Err(Error::msg(format!(
+                "unknown computed field {:?}, expected one of: {}",
+                name,
+                computed::FIELDS
+                    .iter()
+                    .map(|(field, _)| *field)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+
+        if let Some(format) = &config.date_format {
+            date::validate_format(format).map_err(Error::msg)?;
+        }
+
+        if config.base_url.is_none() {
+            config.base_url = book_config
+                .html_config()
+                .and_then(|html| html.site_url)
+                .filter(|url| url.starts_with("http://") || url.starts_with("https://"));
+        }
+
+        if config.language.is_none() {
+            config.language = book_config.book.language.clone();
+        }
+
+        if config.edit_url_template.is_none() {
+            config.edit_url_template = book_config
+                .html_config()
+                .and_then(|html| html.edit_url_template);
+        }
+
+        if config.repository_url.is_none() {
+            config.repository_url = book_config
+                .html_config()
+                .and_then(|html| html.git_repository_url);
+        }
+
+        Ok(config)
+    }
+
+    /// The key an alias stands for, or the key itself.
+    pub(crate) fn canonical_key<'k>(&'k self, key: &'k str) -> &'k str {
+        self.key_aliases
+            .iter()
+            .find(|(_, aliases)| aliases.iter().any(|alias| alias == key))
+            .map_or(key, |(canonical, _)| canonical.as_str())
+    }
+
+    /// What `renderer` gets in place of a frontmatter block: the table for
+    /// html and renderers without a built-in choice, a list for epub (and
+    /// markdown with `markdown_metadata`), nothing for markdown and
+    /// linkcheck.
+    pub(crate) fn output_for(&self, renderer: &str) -> RendererOutput {
+        if let Some(output) = self.renderer_output.get(renderer) {
+            return *output;
+        }
+        match renderer {
+            plain::EPUB_RENDERER => RendererOutput::List,
+            plain::RENDERER if self.markdown_metadata => RendererOutput::List,
+            plain::RENDERER | plain::LINKCHECK_RENDERER => RendererOutput::Strip,
+            _ => RendererOutput::Table,
+        }
+    }
+
+    /// Whether a key holds a date.
+    pub(crate) fn is_date_key(&self, key: &str) -> bool {
+        self.profile.date_keys().contains(&key) || self.date_keys.iter().any(|k| k == key)
+    }
+
+    /// The format for a date key, or `None` if its value is shown as written.
+    pub(crate) fn date_format_for(&self, key: &str) -> Option<&str> {
+        if self.profile.date_keys().contains(&key) {
+            Some(
+                self.date_format
+                    .as_deref()
+                    .unwrap_or(date::DEFAULT_DATE_FORMAT),
+            )
+        } else if self.date_keys.iter().any(|k| k == key) {
+            self.date_format.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+/// Read a table of TOML values as frontmatter values.
+fn scalar_map<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let table = BTreeMap::<String, toml::Value>::deserialize(deserializer)?;
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| (key, value::from_toml(value)))
+        .collect())
+}
+
+/// Order for the chapters of a part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChapterOrder {
+    /// By `date`, newest first.
+    Date,
+    /// By `weight` (or `order`), lowest first.
+    Weight,
+}
+
+/// Handling of chapters past their `expires`/`review_by` date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiredAction {
+    /// Keep the chapter with a "may be outdated" banner.
+    #[default]
+    Banner,
+    /// Remove the chapter (and its sub-chapters) from the build.
+    Remove,
+}
+
+/// How frontmatter problems are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Warn, and skip what can't be parsed.
+    #[default]
+    Lenient,
+    /// Fail the build, listing every problem.
+    Strict,
+}
+
+/// How a chapter that can't be rendered is handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// Fail the build.
+    #[default]
+    Fail,
+    /// Warn, and leave the chapter as written.
+    Warn,
+}
+
+/// Handling of a key written more than once in a frontmatter block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKeys {
+    /// Keep the first value.
+    #[default]
+    First,
+    /// Keep the last value.
+    Last,
+    /// Keep every value, as a comma separated list.
+    List,
+}
+
+/// What a renderer gets in place of a frontmatter block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RendererOutput {
+    /// The html table, with banners, navigation and generated pages.
+    Table,
+    /// A plain `- **key:** value` Markdown list.
+    List,
+    /// Nothing: the block is removed.
+    Strip,
+}
+
+/// How date values are displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateDisplay {
+    /// As written, or formatted with `date_format`.
+    #[default]
+    Absolute,
+    /// "3 months ago", with the absolute date as a tooltip.
+    Relative,
+}
+
+/// Obfuscation applied to `mailto:` links.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailObfuscation {
+    /// Plain addresses.
+    #[default]
+    None,
+    /// Encode the address as HTML character references.
+    Entities,
+    /// Encode the link target and display `user [at] host`.
+    At,
+}
+
+/// A single `[[preprocessor.frontmatter.linkify]]` entry.
+///
+/// Every match of `pattern` inside the value of one of `keys` becomes an
+/// anchor. `replacement` is the link target and supports the usual regex
+/// expansion syntax (`$1`, `${name}`). If the pattern has a capture group
+/// named `link`, only that group is wrapped in the anchor and the rest of the
+/// match is kept as-is.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinkifyRule {
+    /// Regex to search for.
+    pub pattern: String,
+    /// URL template for the anchor's `href`.
+    pub replacement: String,
+    /// Optional template for the anchor text. Defaults to the matched text.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Frontmatter keys the rule applies to. `"*"` matches every key.
+    #[serde(default = "default_linkify_keys")]
+    pub keys: Vec<String>,
+    /// Frontmatter keys the rule never applies to, even if `keys` matches.
+    #[serde(default)]
+    pub skip_keys: Vec<String>,
+}
+
+impl LinkifyRule {
+    /// Create a rule for the default keys.
+    pub(crate) fn new(pattern: &str, replacement: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            text: None,
+            keys: default_linkify_keys(),
+            skip_keys: vec![],
+        }
+    }
+
+    /// Whether the rule should run for the given frontmatter key.
+    pub(crate) fn applies_to(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k == "*" || k == key) && !self.skip_keys.iter().any(|k| k == key)
+    }
+}
+
+/// Rules only apply to "author" unless told otherwise.
+fn default_linkify_keys() -> Vec<String> {
+    vec!["author".to_string()]
+}
@@ -0,0 +1,94 @@
+//! Date handling for frontmatter values.
+use crate::config::DateDisplay;
+use crate::i18n;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+
+/// Format used when a profile formats dates.
+pub(crate) const DEFAULT_DATE_FORMAT: &str = "%B %-d, %Y";
+
+/// Layouts accepted for dates with a time of day.
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+
+/// Layouts accepted for plain dates.
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%B %d, %Y", "%b %d, %Y"];
+
+/// Parse the common ways authors write dates.
+pub(crate) fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.naive_local());
+    }
+
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Render a date value for the table.
+///
+/// Unparseable values are shown as written.
+pub(crate) fn render_date(
+    value: &str,
+    format: Option<&str>,
+    display: DateDisplay,
+    language: Option<&str>,
+) -> String {
+    let Some(date) = parse_date(value) else {
+        return value.to_string();
+    };
+    let absolute = match (format, language) {
+        (Some(format), Some(language)) => date
+            .format(&i18n::localize_format(format, date, language))
+            .to_string(),
+        (Some(format), None) => date.format(format).to_string(),
+        (None, _) => value.to_string(),
+    };
+
+    match display {
+        DateDisplay::Absolute => absolute,
+        DateDisplay::Relative => format!(
+            "<time datetime=\"{}\" title=\"{}\">{}</time>",
+            date.format("%Y-%m-%d"),
+            absolute,
+            relative(date, Local::now().naive_local())
+        ),
+    }
+}
+
+/// Describe `date` relative to `now`, e.g. "3 months ago" or "in 2 days".
+fn relative(date: NaiveDateTime, now: NaiveDateTime) -> String {
+    let days = (now.date() - date.date()).num_days();
+    let (amount, unit) = match days.abs() {
+        0 => return "today".to_string(),
+        1 if days > 0 => return "yesterday".to_string(),
+        1 => return "tomorrow".to_string(),
+        n @ 2..=13 => (n, "day"),
+        n @ 14..=59 => (n / 7, "week"),
+        n @ 60..=364 => (n / 30, "month"),
+        n => (n / 365, "year"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+
+    if days > 0 {
+        format!("{} {}{} ago", amount, unit, plural)
+    } else {
+        format!("in {} {}{}", amount, unit, plural)
+    }
+}
+
+/// Check a `strftime` format string so formatting can't fail later.
+pub(crate) fn validate_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        Err(format!("invalid date format {:?}", format))
+    } else {
+        Ok(())
+    }
+}
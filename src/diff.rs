@@ -0,0 +1,194 @@
+//! Unified diffs of chapter content, for dry runs.
+use mdbook::book::Book;
+use mdbook::BookItem;
+
+/// Lines of context around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Line in both, by index in the old and the new text.
+    Equal(usize, usize),
+    /// Old line removed.
+    Delete(usize),
+    /// New line added.
+    Insert(usize),
+}
+
+/// A diff of every chapter's content from `before` to `after` processing,
+/// matched by path, including chapters left out and pages generated.
+pub(crate) fn book(before: &Book, after: &Book) -> String {
+    let old = chapters(before);
+    let new = chapters(after);
+    let mut diff = String::new();
+    for (path, content) in &old {
+        let processed = new.iter().find(|(p, _)| p == path);
+        diff.push_str(&match processed {
+            Some((_, processed)) => unified(
+                content,
+                processed,
+                &format!("a/{}", path),
+                &format!("b/{}", path),
+            ),
+            None => unified(content, "", &format!("a/{}", path), "/dev/null"),
+        });
+    }
+    for (path, content) in &new {
+        if !old.iter().any(|(p, _)| p == path) {
+            diff.push_str(&unified("", content, "/dev/null", &format!("b/{}", path)));
+        }
+    }
+    diff
+}
+
+/// Each chapter's path (or name, for drafts) and content.
+fn chapters(book: &Book) -> Vec<(String, &str)> {
+    book.iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => Some((
+                chapter
+                    .path
+                    .as_ref()
+                    .map_or_else(|| chapter.name.clone(), |path| path.display().to_string()),
+                chapter.content.as_str(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A unified diff from `old` to `new`, empty if they're the same.
+pub(crate) fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let edits = myers(&a, &b);
+    if edits.iter().all(|edit| matches!(edit, Edit::Equal(..))) {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for hunk in hunks(&edits) {
+        let edits = &edits[hunk.0..hunk.1];
+        let (old_start, new_start) = position(&edits[0]);
+        let old_len = edits
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Insert(_)))
+            .count();
+        let new_len = edits
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Delete(_)))
+            .count();
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        for edit in edits {
+            match *edit {
+                Edit::Equal(i, _) => diff.push_str(&format!(" {}\n", a[i])),
+                Edit::Delete(i) => diff.push_str(&format!("-{}\n", a[i])),
+                Edit::Insert(j) => diff.push_str(&format!("+{}\n", b[j])),
+            }
+        }
+    }
+    diff
+}
+
+/// Shortest edit script between two lists of lines (Myers' algorithm).
+fn myers(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // furthest x reached on each diagonal k, for every edit distance d
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = vec![];
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // walk back from the end through the recorded rounds
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert(prev_y as usize));
+            } else {
+                edits.push(Edit::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    edits
+}
+
+/// Ranges of `edits` to show: each change with its context, merging changes
+/// whose context would overlap.
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Equal(..)) {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// Zero-based old and new line a hunk starts at.
+fn position(edit: &Edit) -> (usize, usize) {
+    match *edit {
+        Edit::Equal(i, j) => (i, j),
+        Edit::Delete(i) => (i, 0),
+        Edit::Insert(j) => (0, j),
+    }
+}
+
+/// `start,len` of a hunk in one-based lines (`start` is the line before for
+/// an empty range).
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
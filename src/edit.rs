@@ -0,0 +1,55 @@
+//! "Edit this page" links to a chapter's source in the forge.
+use crate::config::Config;
+use crate::head::escape;
+use std::path::Path;
+
+/// Where a chapter's source can be edited.
+///
+/// A `source` key is the URL itself and a `repo` key a repository to find the
+/// file in. Otherwise `edit_url_template` has `{path}` (relative to the book
+/// root, like the html renderer's) filled in, or the file is found in
+/// `repository_url`.
+pub(crate) fn edit_url(
+    frontmatter: &[(String, String)],
+    config: &Config,
+    src: &Path,
+    source_path: &Path,
+) -> Option<String> {
+    let value = |key: &str| {
+        frontmatter
+            .iter()
+            .find(|(k, v)| k == key && !v.is_empty())
+            .map(|(_, v)| v.as_str())
+    };
+    if let Some(source) = value("source") {
+        return Some(source.to_string());
+    }
+
+    let path = src.join(source_path).to_string_lossy().replace('\\', "/");
+    match (value("repo"), &config.edit_url_template) {
+        (None, Some(template)) => Some(template.replace("{path}", &path)),
+        (repo, _) => {
+            let repo = repo.or(config.repository_url.as_deref())?;
+            Some(file_url(repo, &path))
+        }
+    }
+}
+
+/// The link shown at the end of the chapter.
+pub(crate) fn edit_link(url: &str, config: &Config) -> String {
+    format!(
+        "\n\n<p class=\"fm-edit\"><a href=\"{}\">{}</a></p>\n",
+        escape(url),
+        config.edit_link_text
+    )
+}
+
+/// A file's page in a GitHub or GitLab repository, on the default branch.
+fn file_url(repo: &str, path: &str) -> String {
+    let repo = repo.trim_end_matches('/').trim_end_matches(".git");
+    if repo.contains("gitlab") {
+        format!("{}/-/blob/HEAD/{}", repo, path)
+    } else {
+        format!("{}/blob/HEAD/{}", repo, path)
+    }
+}
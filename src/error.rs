@@ -0,0 +1,77 @@
+//! Errors the preprocessor stops on.
+//!
+//! They convert into mdbook's error with `?`, so `run()` hands them to
+//! mdbook like any other.
+use std::fmt;
+
+#[derive(Debug)]
+pub(crate) enum FrontmatterError {
+    /// Writing html into a buffer failed.
+    Format(fmt::Error),
+    /// Data couldn't be serialized.
+    Serialize(serde_json::Error),
+    /// A chapter couldn't be rendered.
+    Chapter {
+        chapter: String,
+        source: Box<FrontmatterError>,
+    },
+    /// A block opened on `line` is never closed, in strict mode.
+    Unclosed { line: usize },
+    /// Frontmatter problems, in strict mode.
+    Problems(Vec<String>),
+}
+
+impl FrontmatterError {
+    /// This error, as the reason `chapter` couldn't be rendered.
+    pub(crate) fn in_chapter(self, chapter: String) -> Self {
+        FrontmatterError::Chapter {
+            chapter,
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for FrontmatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontmatterError::Format(_) => write!(f, "unable to write html"),
+            FrontmatterError::Serialize(source) => write!(f, "unable to serialize: {}", source),
+            FrontmatterError::Chapter { chapter, source } => {
+                write!(f, "{}: rendering failed: {}", chapter, source)
+            }
+            FrontmatterError::Unclosed { line } => {
+                write!(f, "frontmatter block on line {} is never closed", line)
+            }
+            FrontmatterError::Problems(problems) => write!(
+                f,
+                "{} frontmatter problem{}:\n{}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" },
+                problems.join("\n")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrontmatterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrontmatterError::Format(source) => Some(source),
+            FrontmatterError::Serialize(source) => Some(source),
+            FrontmatterError::Chapter { source, .. } => Some(source.as_ref()),
+            FrontmatterError::Unclosed { .. } | FrontmatterError::Problems(_) => None,
+        }
+    }
+}
+
+impl From<fmt::Error> for FrontmatterError {
+    fn from(source: fmt::Error) -> Self {
+        FrontmatterError::Format(source)
+    }
+}
+
+impl From<serde_json::Error> for FrontmatterError {
+    fn from(source: serde_json::Error) -> Self {
+        FrontmatterError::Serialize(source)
+    }
+}
@@ -0,0 +1,159 @@
+//! Every chapter's frontmatter as data files, written into the source
+//! directory like every other generated file (see [output](crate::output)).
+use crate::catalog::{Catalog, Entry};
+use crate::config::Config;
+use crate::output;
+use mdbook::errors::Error;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Formats for [FrontmatterPreprocessor::export](crate::FrontmatterPreprocessor::export).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The same object as `frontmatter.json`.
+    Json,
+    /// One table per chapter, named by its path.
+    Toml,
+    /// Comma separated, quoted where needed.
+    Csv,
+    /// Tab separated, with tabs and line breaks in values replaced by spaces.
+    Tsv,
+    /// Just the path of each chapter, one per line.
+    Paths,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Error> {
+        match format {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "paths" => Ok(Self::Paths),
+            _ => Err(Error::msg(format!(
+                "format must be \"json\", \"toml\", \"csv\", \"tsv\" or \"paths\", not {:?}",
+                format
+            ))),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// TSV for `.tsv` paths, CSV otherwise.
+    pub(crate) fn for_path(path: &str) -> Self {
+        if path.ends_with(".tsv") {
+            Self::Tsv
+        } else {
+            Self::Csv
+        }
+    }
+}
+
+/// `{ "<chapter path>": { "<key>": "<value>", .. }, .. }`
+///
+/// Paths are the chapter's source path with `/` separators. A key written
+/// more than once keeps its first value, as everywhere else.
+pub(crate) fn json(catalog: &Catalog) -> Value {
+    let mut chapters = Map::new();
+    for entry in &catalog.entries {
+        chapters.insert(entry.path.to_string_lossy().replace('\\', "/"), keys(entry));
+    }
+    Value::Object(chapters)
+}
+
+/// `{ "<key>": "<value>", .. }` for one chapter.
+fn keys(entry: &Entry) -> Value {
+    let mut keys = Map::new();
+    for (key, value) in &entry.frontmatter {
+        keys.entry(key.clone()).or_insert_with(|| json!(value));
+    }
+    Value::Object(keys)
+}
+
+/// Write the JSON export to `<src_dir>/<metadata_json_path>`.
+pub(crate) fn write_json(catalog: &Catalog, config: &Config, src_dir: &Path) -> Result<(), Error> {
+    let json = serde_json::to_string_pretty(&json(catalog))?;
+    output::write_if_changed(&src_dir.join(&config.metadata_json_path), &json)
+}
+
+/// Write each chapter's keys to `<chapter>.frontmatter.json` next to its
+/// source, e.g. `guide/intro.frontmatter.json`, which the build copies next
+/// to `guide/intro.html`.
+pub(crate) fn write_sidecars(catalog: &Catalog, src_dir: &Path) -> Result<(), Error> {
+    for entry in &catalog.entries {
+        let json = serde_json::to_string_pretty(&keys(entry))?;
+        let path = src_dir.join(entry.path.with_extension("frontmatter.json"));
+        output::write_if_changed(&path, &json)?;
+    }
+    Ok(())
+}
+
+/// The whole export in `format`.
+pub(crate) fn render(catalog: &Catalog, format: ExportFormat) -> Result<String, Error> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(&json(catalog))? + "\n"),
+        ExportFormat::Toml => toml::to_string(&json(catalog))
+            .map_err(|e| Error::msg(format!("can't write frontmatter as toml: {}", e))),
+        ExportFormat::Csv | ExportFormat::Tsv => Ok(table(catalog, format)),
+        ExportFormat::Paths => Ok(catalog
+            .entries
+            .iter()
+            .map(|entry| entry.path.to_string_lossy().replace('\\', "/") + "\n")
+            .collect()),
+    }
+}
+
+/// One row per chapter and one column per key, after `path` and `title`.
+///
+/// Columns are in the order keys are first seen in the book.
+pub(crate) fn table(catalog: &Catalog, format: ExportFormat) -> String {
+    let mut columns: Vec<&str> = vec![];
+    for entry in &catalog.entries {
+        for (key, _) in &entry.frontmatter {
+            if !columns.contains(&key.as_str()) && key != "title" {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut rows = vec![["path", "title"]
+        .into_iter()
+        .chain(columns.iter().copied())
+        .map(|cell| field(cell, format))
+        .collect::<Vec<_>>()];
+    for entry in &catalog.entries {
+        let path = entry.path.to_string_lossy().replace('\\', "/");
+        let mut row = vec![field(&path, format), field(&entry.title, format)];
+        row.extend(
+            columns
+                .iter()
+                .map(|key| field(entry.value(key).unwrap_or_default(), format)),
+        );
+        rows.push(row);
+    }
+
+    let separator = match format {
+        ExportFormat::Tsv => "\t",
+        _ => ",",
+    };
+    rows.iter().map(|row| row.join(separator) + "\n").collect()
+}
+
+/// Write the table export to `<src_dir>/<csv_path>`.
+pub(crate) fn write_table(catalog: &Catalog, config: &Config, src_dir: &Path) -> Result<(), Error> {
+    let format = ExportFormat::for_path(&config.csv_path);
+    output::write_if_changed(&src_dir.join(&config.csv_path), &table(catalog, format))
+}
+
+fn field(value: &str, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Tsv => value.replace(['\t', '\n', '\r'], " "),
+        _ if value.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        }
+        _ => value.to_string(),
+    }
+}
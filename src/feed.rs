@@ -0,0 +1,318 @@
+//! Feeds of the book's dated chapters.
+//!
+//! Like redirect stubs, feeds are written into the source directory and
+//! copied into the build by the html renderer.
+use crate::catalog::{Catalog, Entry};
+use crate::config::Config;
+use crate::date;
+use crate::output;
+use crate::taxonomy::AUTHOR_KEYS;
+use crate::value::author_name;
+use chrono::{NaiveDateTime, SecondsFormat};
+use mdbook::errors::Error;
+use std::path::Path;
+
+/// Book-level details the feeds need.
+pub(crate) struct Channel<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) description: Option<&'a str>,
+    pub(crate) base_url: &'a str,
+    pub(crate) authors: &'a [String],
+}
+
+/// Chapters with a `date`, newest first, at most `feed_limit` of them.
+fn items<'c>(catalog: &'c Catalog, config: &Config) -> Vec<(NaiveDateTime, &'c Entry)> {
+    let mut items: Vec<_> = catalog
+        .entries
+        .iter()
+        .filter_map(|entry| Some((date::parse_date(entry.value("date")?)?, entry)))
+        .collect();
+    items.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    items.truncate(config.feed_limit);
+    items
+}
+
+/// Write an RSS 2.0 feed to `<src_dir>/<rss_path>`.
+pub(crate) fn write_rss(
+    catalog: &Catalog,
+    config: &Config,
+    channel: &Channel,
+    src_dir: &Path,
+) -> Result<(), Error> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!("<title>{}</title>\n", escape(channel.title)));
+    xml.push_str(&format!("<link>{}</link>\n", escape(channel.base_url)));
+    xml.push_str(&format!(
+        "<description>{}</description>\n",
+        escape(channel.description.unwrap_or(channel.title))
+    ));
+
+    for (date, entry) in items(catalog, config) {
+        let url = output::page_url(channel.base_url, &entry.path);
+        xml.push_str("<item>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape(&entry.title)));
+        xml.push_str(&format!("<link>{}</link>\n", escape(&url)));
+        xml.push_str(&format!(
+            "<guid isPermaLink=\"true\">{}</guid>\n",
+            escape(&url)
+        ));
+        xml.push_str(&format!(
+            "<pubDate>{}</pubDate>\n",
+            date.and_utc().to_rfc2822()
+        ));
+        if let Some(description) = entry.value("description") {
+            xml.push_str(&format!(
+                "<description>{}</description>\n",
+                escape(description)
+            ));
+        }
+        xml.push_str("</item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+
+    output::write_if_changed(&src_dir.join(&config.rss_path), &xml)
+}
+
+/// Write an Atom 1.0 feed to `<src_dir>/<atom_path>`.
+///
+/// Entries are `updated` at their `updated` key, or their `date`.
+pub(crate) fn write_atom(
+    catalog: &Catalog,
+    config: &Config,
+    channel: &Channel,
+    src_dir: &Path,
+) -> Result<(), Error> {
+    let items: Vec<_> = items(catalog, config)
+        .into_iter()
+        .map(|(published, entry)| {
+            let updated = entry
+                .value("updated")
+                .and_then(date::parse_date)
+                .unwrap_or(published);
+            (published, updated, entry)
+        })
+        .collect();
+    let feed_updated = items.iter().map(|(_, updated, _)| *updated).max();
+    let feed_url = format!(
+        "{}/{}",
+        channel.base_url.trim_end_matches('/'),
+        config.atom_path
+    );
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("<title>{}</title>\n", escape(channel.title)));
+    if let Some(description) = channel.description {
+        xml.push_str(&format!("<subtitle>{}</subtitle>\n", escape(description)));
+    }
+    xml.push_str(&format!("<id>{}</id>\n", escape(&feed_url)));
+    xml.push_str(&format!(
+        "<link rel=\"self\" href=\"{}\"/>\n",
+        escape(&feed_url)
+    ));
+    xml.push_str(&format!("<link href=\"{}\"/>\n", escape(channel.base_url)));
+    if let Some(updated) = feed_updated {
+        xml.push_str(&format!("<updated>{}</updated>\n", timestamp(updated)));
+    }
+    for author in channel.authors {
+        xml.push_str(&author_element(author));
+    }
+
+    for (published, updated, entry) in items {
+        let url = output::page_url(channel.base_url, &entry.path);
+        xml.push_str("<entry>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape(&entry.title)));
+        xml.push_str(&format!("<id>{}</id>\n", escape(&url)));
+        xml.push_str(&format!("<link href=\"{}\"/>\n", escape(&url)));
+        xml.push_str(&format!(
+            "<published>{}</published>\n",
+            timestamp(published)
+        ));
+        xml.push_str(&format!("<updated>{}</updated>\n", timestamp(updated)));
+        for key in AUTHOR_KEYS {
+            for author in entry.list(key) {
+                xml.push_str(&author_element(author_name(&author)));
+            }
+        }
+        if let Some(description) = entry.value("description") {
+            xml.push_str(&format!("<summary>{}</summary>\n", escape(description)));
+        }
+        xml.push_str("</entry>\n");
+    }
+    xml.push_str("</feed>\n");
+
+    output::write_if_changed(&src_dir.join(&config.atom_path), &xml)
+}
+
+fn author_element(name: &str) -> String {
+    format!("<author><name>{}</name></author>\n", escape(name))
+}
+
+/// RFC 3339 timestamp, dates without a zone taken as UTC.
+fn timestamp(date: NaiveDateTime) -> String {
+    date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Escape text for XML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::entry;
+    use std::fs;
+
+    fn catalog() -> Catalog {
+        Catalog {
+            entries: vec![
+                entry(
+                    "old.md",
+                    &[
+                        ("title", "Old"),
+                        ("date", "2024-01-05"),
+                        ("updated", "2024-03-01"),
+                    ],
+                ),
+                entry("undated.md", &[("title", "Undated")]),
+                entry(
+                    "posts/new.md",
+                    &[
+                        ("title", "Fish & <Chips>"),
+                        ("date", "2024-02-10"),
+                        ("description", "\"Quoted\" & 'single'"),
+                        ("author", "Ann (@ann), Bob"),
+                    ],
+                ),
+                entry("older.md", &[("title", "Older"), ("date", "2023-12-24")]),
+            ],
+        }
+    }
+
+    const CHANNEL: Channel = Channel {
+        title: "Tom & Jerry's book",
+        description: None,
+        base_url: "https://example.com/book/",
+        authors: &[],
+    };
+
+    /// The feed `write` puts into a fresh source directory.
+    fn written(
+        config: &Config,
+        write: fn(&Catalog, &Config, &Channel, &Path) -> Result<(), Error>,
+        path: &str,
+    ) -> String {
+        let src = tempfile::tempdir().unwrap();
+        write(&catalog(), config, &CHANNEL, src.path()).unwrap();
+        fs::read_to_string(src.path().join(path)).unwrap()
+    }
+
+    /// The text of every `<tag>` in `xml`, in order.
+    fn all<'x>(xml: &'x str, tag: &str) -> Vec<&'x str> {
+        xml.split(&format!("<{}>", tag))
+            .skip(1)
+            .filter_map(|rest| rest.split_once(&format!("</{}>", tag)))
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    #[test]
+    fn rss_lists_dated_chapters_newest_first() {
+        let xml = written(&Config::default(), write_rss, "rss.xml");
+        assert_eq!(
+            all(&xml, "title"),
+            [
+                "Tom &amp; Jerry&apos;s book",
+                "Fish &amp; &lt;Chips&gt;",
+                "Old",
+                "Older"
+            ]
+        );
+        assert_eq!(
+            all(&xml, "link")[1],
+            "https://example.com/book/posts/new.html"
+        );
+        assert_eq!(
+            all(&xml, "pubDate"),
+            [
+                "Sat, 10 Feb 2024 00:00:00 +0000",
+                "Fri, 5 Jan 2024 00:00:00 +0000",
+                "Sun, 24 Dec 2023 00:00:00 +0000"
+            ]
+        );
+        // the channel falls back to the title, the item has its own
+        assert_eq!(
+            all(&xml, "description"),
+            [
+                "Tom &amp; Jerry&apos;s book",
+                "&quot;Quoted&quot; &amp; &apos;single&apos;"
+            ]
+        );
+    }
+
+    #[test]
+    fn rss_keeps_the_newest_feed_limit_chapters() {
+        let config = Config {
+            feed_limit: 2,
+            ..Config::default()
+        };
+        let xml = written(&config, write_rss, "rss.xml");
+        assert_eq!(all(&xml, "title")[1..], ["Fish &amp; &lt;Chips&gt;", "Old"]);
+    }
+
+    #[test]
+    fn atom_entries_are_updated_at_updated_or_date() {
+        let channel = Channel {
+            description: Some("News <weekly>"),
+            authors: &["Editor & Co".to_string()],
+            ..CHANNEL
+        };
+        let src = tempfile::tempdir().unwrap();
+        write_atom(&catalog(), &Config::default(), &channel, src.path()).unwrap();
+        let xml = fs::read_to_string(src.path().join("atom.xml")).unwrap();
+
+        assert_eq!(all(&xml, "subtitle"), ["News &lt;weekly&gt;"]);
+        assert_eq!(all(&xml, "id")[0], "https://example.com/book/atom.xml");
+        // the feed is as new as its newest update, not its newest entry
+        assert_eq!(
+            all(&xml, "updated"),
+            [
+                "2024-03-01T00:00:00Z",
+                "2024-02-10T00:00:00Z",
+                "2024-03-01T00:00:00Z",
+                "2023-12-24T00:00:00Z"
+            ]
+        );
+        assert_eq!(
+            all(&xml, "published"),
+            [
+                "2024-02-10T00:00:00Z",
+                "2024-01-05T00:00:00Z",
+                "2023-12-24T00:00:00Z"
+            ]
+        );
+        assert_eq!(all(&xml, "name"), ["Editor &amp; Co", "Ann", "Bob"]);
+        assert_eq!(
+            all(&xml, "summary"),
+            ["&quot;Quoted&quot; &amp; &apos;single&apos;"]
+        );
+    }
+
+    #[test]
+    fn atom_without_dated_chapters_has_no_updated() {
+        let src = tempfile::tempdir().unwrap();
+        let catalog = Catalog {
+            entries: vec![entry("undated.md", &[])],
+        };
+        write_atom(&catalog, &Config::default(), &CHANNEL, src.path()).unwrap();
+        let xml = fs::read_to_string(src.path().join("atom.xml")).unwrap();
+        assert!(all(&xml, "updated").is_empty(), "{}", xml);
+        assert!(!xml.contains("<entry>"), "{}", xml);
+    }
+}
@@ -0,0 +1,62 @@
+//! Decide which chapters make it into the build.
+use crate::date;
+use crate::value::{is_true, split_list};
+use chrono::NaiveDateTime;
+use mdbook::book::Chapter;
+use mdbook::BookItem;
+
+/// Keep only the chapters for which `keep` returns true.
+///
+/// A removed chapter takes its sub-chapters with it.
+pub(crate) fn retain_chapters(items: &mut Vec<BookItem>, keep: &mut impl FnMut(&Chapter) -> bool) {
+    items.retain(|item| match item {
+        BookItem::Chapter(chapter) => keep(chapter),
+        _ => true,
+    });
+
+    for item in items {
+        if let BookItem::Chapter(chapter) = item {
+            retain_chapters(&mut chapter.sub_items, keep);
+        }
+    }
+}
+
+/// When a chapter becomes public, from its `publish_date` key.
+pub(crate) fn publish_date(frontmatter: &[(String, String)]) -> Option<NaiveDateTime> {
+    date_value(frontmatter, "publish_date")
+}
+
+/// The earliest of `expires` and `review_by`, with the key it came from.
+pub(crate) fn expiry(frontmatter: &[(String, String)]) -> Option<(&'static str, NaiveDateTime)> {
+    ["expires", "review_by"]
+        .into_iter()
+        .filter_map(|key| date_value(frontmatter, key).map(|date| (key, date)))
+        .min_by_key(|(_, date)| *date)
+}
+
+/// Whether a chapter is `deprecated: true` or `status: Deprecated`.
+pub(crate) fn deprecated(frontmatter: &[(String, String)]) -> bool {
+    frontmatter.iter().any(|(key, value)| {
+        (key == "deprecated" && is_true(value))
+            || (key == "status" && value.trim().eq_ignore_ascii_case("deprecated"))
+    })
+}
+
+/// Whether a chapter's `audience` (if any) includes the build's audience.
+pub(crate) fn for_audience(frontmatter: &[(String, String)], audience: &str) -> bool {
+    frontmatter
+        .iter()
+        .filter(|(key, _)| key == "audience")
+        .all(|(_, value)| {
+            split_list(value)
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(audience) || a.eq_ignore_ascii_case("all"))
+        })
+}
+
+fn date_value(frontmatter: &[(String, String)], key: &str) -> Option<NaiveDateTime> {
+    frontmatter
+        .iter()
+        .find(|(k, _)| k == key)
+        .and_then(|(_, value)| date::parse_date(value))
+}
@@ -0,0 +1,49 @@
+//! Support for the mdbook-i18n-helpers gettext workflow.
+//!
+//! `mdbook-xgettext` extracts every paragraph of the chapter source as a
+//! message, so a frontmatter block reaches translators as one message. The
+//! block is left byte-for-byte intact (otherwise the message ids extracted
+//! here would no longer match the source `mdbook-gettext` translates) and a
+//! directive in front of it tells translators what to keep.
+use serde::Deserialize;
+use std::ops::Range;
+
+/// The renderer `mdbook-xgettext` registers as.
+pub(crate) const RENDERER: &str = "xgettext";
+
+/// How frontmatter blocks are presented to translators.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GettextMode {
+    /// Add a translator comment explaining the block.
+    #[default]
+    Comment,
+    /// Exclude the block from extraction so it is never translated.
+    Skip,
+}
+
+/// Put a directive in front of each frontmatter block in `ranges`.
+pub(crate) fn mark_frontmatter(
+    content: &str,
+    ranges: &[Range<usize>],
+    mode: GettextMode,
+) -> String {
+    let directive = match mode {
+        GettextMode::Comment => {
+            "<!-- i18n:comment: Frontmatter. Keep the \"+++\" lines and the keys \
+             before each \":\" unchanged, translate only the values. -->"
+        }
+        GettextMode::Skip => "<!-- i18n:skip -->",
+    };
+
+    let mut marked = String::with_capacity(content.len() + ranges.len() * directive.len());
+    let mut written = 0;
+    for range in ranges {
+        marked.push_str(&content[written..range.start]);
+        marked.push_str(directive);
+        marked.push_str("\n\n");
+        written = range.start;
+    }
+    marked.push_str(&content[written..]);
+    marked
+}
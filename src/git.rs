@@ -0,0 +1,103 @@
+//! Keys read from the git history of a chapter's source file.
+use crate::config::Config;
+use crate::parse;
+use crate::taxonomy::AUTHOR_KEYS;
+use crate::value::{author_name, split_list};
+use log::warn;
+use mdbook::book::{Book, Chapter};
+use mdbook::BookItem;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The git-derived keys of every chapter, by chapter source path.
+///
+/// Files git doesn't track get none. If git can't be run at all, that's
+/// reported once and no chapter gets any.
+pub(crate) fn history(
+    config: &Config,
+    book: &Book,
+    src_dir: &Path,
+) -> HashMap<PathBuf, Vec<(String, String)>> {
+    let mut history = HashMap::new();
+    if !config.git_updated && !config.git_contributors {
+        return history;
+    }
+
+    for item in book.iter() {
+        let BookItem::Chapter(Chapter {
+            source_path: Some(source_path),
+            content,
+            ..
+        }) = item
+        else {
+            continue;
+        };
+
+        let keys = match chapter_keys(config, src_dir, source_path, content) {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("not reading git history: {}", e);
+                return HashMap::new();
+            }
+        };
+        history.insert(source_path.clone(), keys);
+    }
+    history
+}
+
+/// The git-derived keys of one chapter, given its content.
+fn chapter_keys(
+    config: &Config,
+    src_dir: &Path,
+    source_path: &Path,
+    content: &str,
+) -> std::io::Result<Vec<(String, String)>> {
+    let mut keys = vec![];
+    if config.git_updated {
+        let dates = git_log(src_dir, source_path, &["-1", "--format=%cs"])?;
+        keys.extend(
+            dates
+                .lines()
+                .next()
+                .map(|date| ("updated".to_string(), date.to_string())),
+        );
+    }
+
+    if config.git_contributors {
+        // everyone who committed to the file but isn't a declared author
+        let declared: Vec<String> = parse::read_frontmatter(content, config)
+            .iter()
+            .filter(|(key, _)| AUTHOR_KEYS.contains(&key.as_str()))
+            .flat_map(|(_, value)| split_list(value))
+            .map(|author| author_name(&author).to_lowercase())
+            .collect();
+        let mut contributors: Vec<&str> = vec![];
+        let names = git_log(src_dir, source_path, &["--format=%aN"])?;
+        for name in names.lines().rev().map(str::trim) {
+            if !name.is_empty()
+                && !contributors.contains(&name)
+                && !declared.contains(&name.to_lowercase())
+            {
+                contributors.push(name);
+            }
+        }
+        if !contributors.is_empty() {
+            keys.push(("contributors".to_string(), contributors.join(", ")));
+        }
+    }
+    Ok(keys)
+}
+
+/// `git log` for one file, newest commit first.
+fn git_log(src_dir: &Path, source_path: &Path, args: &[&str]) -> std::io::Result<String> {
+    let output = Command::new("git")
+        .arg("log")
+        .args(args)
+        .arg("--")
+        .arg(source_path)
+        .current_dir(src_dir)
+        .output()?;
+    // outside a repository there's simply no history
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,373 @@
+//! Per-chapter tags for the page `<head>`.
+//!
+//! A preprocessor can't reach the page head directly, so the tags are written
+//! into the theme's `head.hbs` partial. Each chapter's tags are wrapped in a
+//! handlebars condition on the page `path`, and the generated section sits
+//! between markers so anything else in the partial is kept.
+use crate::config::Config;
+use crate::date;
+use crate::error::FrontmatterError;
+use crate::output;
+use crate::value::{author_name, is_true, split_list};
+use chrono::NaiveTime;
+use mdbook::book::{Book, Chapter};
+use mdbook::errors::Error;
+use mdbook::BookItem;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const START_MARKER: &str = "{{!-- frontmatter:start (generated, do not edit) --}}";
+const END_MARKER: &str = "{{!-- frontmatter:end --}}";
+
+/// Head tags collected for every chapter.
+pub(crate) struct HeadTags {
+    /// The book title, used as the site name.
+    site_name: Option<String>,
+    /// The chapter the html renderer also writes as `index.html`.
+    index: Option<PathBuf>,
+    /// Chapter path (as the html renderer sees it) and its tags.
+    chapters: Vec<(String, Vec<String>)>,
+}
+
+impl HeadTags {
+    pub(crate) fn new(site_name: Option<String>, book: &Book) -> Self {
+        // the first chapter with a page, prefix chapters included
+        let index = book.iter().find_map(|item| match item {
+            BookItem::Chapter(chapter) => chapter.path.clone(),
+            _ => None,
+        });
+        Self {
+            site_name,
+            index,
+            chapters: vec![],
+        }
+    }
+
+    /// Collect the head tags for a chapter.
+    pub(crate) fn collect(
+        &mut self,
+        chapter: &Chapter,
+        frontmatter: &[(String, String)],
+        config: &Config,
+    ) -> Result<(), FrontmatterError> {
+        let Some(path) = &chapter.path else {
+            return Ok(());
+        };
+        let value = |key: &str| {
+            frontmatter
+                .iter()
+                .find(|(k, v)| k == key && !v.is_empty())
+                .map(|(_, v)| v.as_str())
+        };
+
+        // a canonical URL points crawlers (and shares) at the original page
+        let canonical = value("canonical").map(|url| absolute_url(url, config));
+        let url = canonical.clone().or_else(|| {
+            config
+                .base_url
+                .as_ref()
+                .map(|base_url| output::page_url(base_url, path))
+        });
+
+        let mut tags = vec![];
+        if let Some(canonical) = &canonical {
+            tags.push(format!(
+                "<link rel=\"canonical\" href=\"{}\">",
+                escape(canonical)
+            ));
+        }
+        if let Some(description) = value("description") {
+            tags.push(meta("name", "description", description));
+        }
+        match (value("robots"), value("noindex").is_some_and(is_true)) {
+            (Some(robots), _) => tags.push(meta("name", "robots", robots)),
+            (None, true) => tags.push(meta("name", "robots", "noindex")),
+            (None, false) => (),
+        }
+
+        let mut keywords: Vec<String> = vec![];
+        for key in ["keywords", "tags"] {
+            for keyword in value(key).map(split_list).unwrap_or_default() {
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+        }
+        if !keywords.is_empty() {
+            tags.push(meta("name", "keywords", &keywords.join(", ")));
+        }
+
+        if config.open_graph {
+            tags.push(meta("property", "og:title", &chapter.name));
+            if let Some(description) = value("description") {
+                tags.push(meta("property", "og:description", description));
+            }
+            tags.push(meta(
+                "property",
+                "og:type",
+                value("og_type").unwrap_or("article"),
+            ));
+            if let Some(url) = &url {
+                tags.push(meta("property", "og:url", url));
+            }
+            if let Some(site_name) = &self.site_name {
+                tags.push(meta("property", "og:site_name", site_name));
+            }
+        }
+
+        let image = value("image").map(|image| absolute_url(image, config));
+        if let (true, Some(image)) = (config.open_graph, &image) {
+            tags.push(meta("property", "og:image", image));
+        }
+
+        if config.twitter_cards {
+            let card = if image.is_some() {
+                "summary_large_image"
+            } else {
+                "summary"
+            };
+            tags.push(meta("name", "twitter:card", card));
+            if let Some(site) = &config.twitter_site {
+                tags.push(meta("name", "twitter:site", site));
+            }
+            if let Some(creator) = value("twitter")
+                .map(str::to_string)
+                .or_else(|| value("author").and_then(author_handle))
+            {
+                tags.push(meta("name", "twitter:creator", &creator));
+            }
+            tags.push(meta("name", "twitter:title", &chapter.name));
+            if let Some(description) = value("description") {
+                tags.push(meta("name", "twitter:description", description));
+            }
+            if let Some(image) = &image {
+                tags.push(meta("name", "twitter:image", image));
+            }
+        }
+
+        if config.json_ld {
+            let mut article = json!({
+                "@context": "https://schema.org",
+                "@type": "Article",
+                "headline": chapter.name,
+            });
+            let authors: Vec<_> = value("author")
+                .map(split_list)
+                .unwrap_or_default()
+                .iter()
+                .map(|author| json!({ "@type": "Person", "name": author_name(author) }))
+                .collect();
+            if !authors.is_empty() {
+                article["author"] = json!(authors);
+            }
+            let published = value("date").or_else(|| value("created"));
+            if let Some(date) = published.and_then(iso_date) {
+                article["datePublished"] = json!(date);
+            }
+            if let Some(date) = value("updated").and_then(iso_date) {
+                article["dateModified"] = json!(date);
+            }
+            if let Some(description) = value("description") {
+                article["description"] = json!(description);
+            }
+            if let Some(image) = &image {
+                article["image"] = json!(image);
+            }
+            if let Some(url) = &url {
+                article["url"] = json!(url);
+            }
+            tags.push(json_ld(&article)?);
+        }
+
+        // the first chapter is rendered again as index.html
+        if self.index.as_ref() == Some(path) {
+            self.chapters.push(("index.md".to_string(), tags.clone()));
+        }
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.chapters.push((path, tags));
+        Ok(())
+    }
+
+    /// Write the generated section into `<theme_dir>/head.hbs`.
+    pub(crate) fn write(&self, theme_dir: &Path) -> Result<(), Error> {
+        let mut section = String::from(START_MARKER);
+        section.push('\n');
+        for (path, tags) in self.chapters.iter().filter(|(_, tags)| !tags.is_empty()) {
+            section.push_str(&format!("{{{{#if (eq path \"{}\")}}}}\n", escape(path)));
+            for tag in tags {
+                section.push_str(tag);
+                section.push('\n');
+            }
+            section.push_str("{{/if}}\n");
+        }
+        section.push_str(END_MARKER);
+
+        let path = theme_dir.join("head.hbs");
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let partial = match (existing.find(START_MARKER), existing.find(END_MARKER)) {
+            (Some(start), Some(end)) if start < end => format!(
+                "{}{}{}",
+                &existing[..start],
+                section,
+                &existing[end + END_MARKER.len()..]
+            ),
+            _ if existing.is_empty() => format!("{}\n", section),
+            _ => format!("{}\n{}\n", existing.trim_end(), section),
+        };
+
+        output::write_if_changed(&path, &partial)
+    }
+}
+
+/// The first `(@handle)` in an author value, e.g. "@alice".
+fn author_handle(author: &str) -> Option<String> {
+    let start = author.find("(@")? + 1;
+    let end = start + author[start..].find(')')?;
+    let handle = &author[start..end];
+    (handle.len() > 1).then(|| handle.to_string())
+}
+
+/// ISO 8601 date (with time if it has one) for structured data.
+fn iso_date(value: &str) -> Option<String> {
+    let date = date::parse_date(value)?;
+    let format = if date.time() == NaiveTime::MIN {
+        "%Y-%m-%d"
+    } else {
+        "%Y-%m-%dT%H:%M:%S"
+    };
+    Some(date.format(format).to_string())
+}
+
+/// `<script type="application/ld+json">` block.
+///
+/// Pretty printing puts every structural brace on its own line, so any `{{`
+/// left is inside a string and can be escaped for handlebars without
+/// changing the data. `</` is escaped so a value can't close the script.
+fn json_ld(data: &serde_json::Value) -> Result<String, FrontmatterError> {
+    let json = serde_json::to_string_pretty(data)?
+        .replace("{{", "{\\u007b")
+        .replace("}}", "}\\u007d")
+        .replace("</", "<\\/");
+    Ok(format!(
+        "<script type=\"application/ld+json\">\n{}\n</script>",
+        json
+    ))
+}
+
+/// Make a root-relative path absolute using `base_url`.
+fn absolute_url(url: &str, config: &Config) -> String {
+    match &config.base_url {
+        Some(base_url) if !url.contains("://") => format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        ),
+        _ => url.to_string(),
+    }
+}
+
+/// `<meta {attr}="{name}" content="{content}">`
+fn meta(attr: &str, name: &str, content: &str) -> String {
+    format!(
+        "<meta {}=\"{}\" content=\"{}\">",
+        attr,
+        escape(name),
+        escape(content)
+    )
+}
+
+/// Escape a value for an html attribute inside a handlebars template.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // keep handlebars from seeing `{{`
+            '{' => escaped.push_str("&#123;"),
+            '}' => escaped.push_str("&#125;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head_tags(frontmatter: &[(&str, &str)]) -> HeadTags {
+        let chapter = Chapter::new("Intro", String::new(), "intro.md", vec![]);
+        let mut book = Book::new();
+        book.sections = vec![BookItem::Chapter(chapter.clone())];
+        let frontmatter: Vec<_> = frontmatter
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let mut tags = HeadTags::new(Some("Book".to_string()), &book);
+        tags.collect(&chapter, &frontmatter, &Config::default())
+            .unwrap();
+        tags
+    }
+
+    #[test]
+    fn text_outside_the_markers_is_kept() {
+        let theme = tempfile::tempdir().unwrap();
+        let path = theme.path().join("head.hbs");
+        let stale = format!(
+            "<link rel=\"icon\">\n{}\nstale\n{}\n<!-- end -->\n",
+            START_MARKER, END_MARKER
+        );
+        fs::write(&path, stale).unwrap();
+
+        head_tags(&[("description", "About")])
+            .write(theme.path())
+            .unwrap();
+        let partial = fs::read_to_string(&path).unwrap();
+        assert!(partial.starts_with(&format!("<link rel=\"icon\">\n{}\n", START_MARKER)));
+        assert!(partial.ends_with(&format!("{}\n<!-- end -->\n", END_MARKER)));
+        assert!(!partial.contains("stale"));
+        assert!(partial.contains("{{#if (eq path \"intro.md\")}}"));
+        assert!(partial.contains("<meta name=\"description\" content=\"About\">"));
+    }
+
+    #[test]
+    fn second_write_changes_nothing() {
+        let theme = tempfile::tempdir().unwrap();
+        let path = theme.path().join("head.hbs");
+        fs::write(&path, "<link rel=\"icon\">\n").unwrap();
+
+        let tags = head_tags(&[("description", "About")]);
+        tags.write(theme.path()).unwrap();
+        let first = fs::read_to_string(&path).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        tags.write(theme.path()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+        assert_eq!(first.matches(START_MARKER).count(), 1);
+    }
+
+    #[test]
+    fn json_ld_cannot_close_its_script() {
+        let theme = tempfile::tempdir().unwrap();
+        head_tags(&[("description", "</script><script>alert(1)</script> {{x}}")])
+            .write(theme.path())
+            .unwrap();
+        let partial = fs::read_to_string(theme.path().join("head.hbs")).unwrap();
+        let script = partial
+            .split("<script type=\"application/ld+json\">\n")
+            .nth(1)
+            .unwrap();
+        let json = &script[..script.find("\n</script>").unwrap()];
+        assert!(!json.contains("</"));
+        assert!(!json.contains("{{"));
+        let data: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            data["description"],
+            "</script><script>alert(1)</script> {{x}}"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Translated labels and month names for non-English books.
+use chrono::{Datelike, NaiveDateTime};
+
+/// Candidate language tags, most specific first: "fr-CA" then "fr".
+pub(crate) fn candidates(language: &str) -> impl Iterator<Item = &str> {
+    let primary = language.split(['-', '_']).next().unwrap_or(language);
+    std::iter::once(language).chain((primary != language).then_some(primary))
+}
+
+/// Built-in header text for common keys.
+pub(crate) fn builtin_label(language: &str, key: &str) -> Option<&'static str> {
+    let labels: &[(&str, &str)] = match language {
+        "fr" => &[
+            ("author", "Auteur"),
+            ("authors", "Auteurs"),
+            ("date", "Date"),
+            ("created", "Créé le"),
+            ("updated", "Mis à jour le"),
+            ("status", "Statut"),
+            ("title", "Titre"),
+            ("version", "Version"),
+            ("tags", "Étiquettes"),
+            ("description", "Description"),
+            ("start-date", "Date de début"),
+            ("tracking-issue", "Ticket de suivi"),
+            ("superseded-by", "Remplacé par"),
+            ("reading time", "Temps de lecture"),
+        ],
+        "de" => &[
+            ("author", "Autor"),
+            ("authors", "Autoren"),
+            ("date", "Datum"),
+            ("created", "Erstellt"),
+            ("updated", "Aktualisiert"),
+            ("status", "Status"),
+            ("title", "Titel"),
+            ("version", "Version"),
+            ("tags", "Schlagwörter"),
+            ("description", "Beschreibung"),
+            ("start-date", "Startdatum"),
+            ("tracking-issue", "Tracking-Issue"),
+            ("superseded-by", "Ersetzt durch"),
+            ("reading time", "Lesezeit"),
+        ],
+        "es" => &[
+            ("author", "Autor"),
+            ("authors", "Autores"),
+            ("date", "Fecha"),
+            ("created", "Creado"),
+            ("updated", "Actualizado"),
+            ("status", "Estado"),
+            ("title", "Título"),
+            ("version", "Versión"),
+            ("tags", "Etiquetas"),
+            ("description", "Descripción"),
+            ("start-date", "Fecha de inicio"),
+            ("tracking-issue", "Issue de seguimiento"),
+            ("superseded-by", "Reemplazado por"),
+            ("reading time", "Tiempo de lectura"),
+        ],
+        _ => &[],
+    };
+
+    labels
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, label)| *label)
+}
+
+/// Full and abbreviated month names.
+fn month_names(language: &str) -> Option<([&'static str; 12], [&'static str; 12])> {
+    match language {
+        "fr" => Some((
+            [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            [
+                "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+                "nov.", "déc.",
+            ],
+        )),
+        "de" => Some((
+            [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            [
+                "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.",
+                "Nov.", "Dez.",
+            ],
+        )),
+        "es" => Some((
+            [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+            [
+                "ene.", "feb.", "mar.", "abr.", "may.", "jun.", "jul.", "ago.", "sept.", "oct.",
+                "nov.", "dic.",
+            ],
+        )),
+        _ => None,
+    }
+}
+
+/// Replace `%B`/`%b` in a `strftime` format with the translated month name.
+///
+/// chrono only knows English names, so the names are spliced into the format
+/// before it is handed over.
+pub(crate) fn localize_format(format: &str, date: NaiveDateTime, language: &str) -> String {
+    let Some((full, short)) = candidates(language).find_map(month_names) else {
+        return format.to_string();
+    };
+    let month = date.month0() as usize;
+
+    let mut localized = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('B') => localized.push_str(full[month]),
+            Some('b') | Some('h') => localized.push_str(short[month]),
+            Some(other) => {
+                localized.push('%');
+                localized.push(other);
+            }
+            None => localized.push('%'),
+        }
+    }
+    localized
+}
@@ -0,0 +1,45 @@
+//! `{{#include}}` directives the links preprocessor hasn't expanded yet.
+//!
+//! mdbook runs preprocessors without `before`/`after` in name order, so this
+//! one sees `{{#include}}` as written and the included text only shows up
+//! after it. Frontmatter in an included file then reaches the page raw.
+use crate::config::Config;
+use crate::parse;
+use log::debug;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// `{{#include path}}` and `{{#rustdoc_include path}}`, anchors and line
+/// ranges after the path.
+static DIRECTIVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?P<escape>\\)?\{\{\s*#(?:include|rustdoc_include)\s+(?P<path>[^\s:}]+)")
+        .expect("valid regex")
+});
+
+/// The files `content` includes that have frontmatter of their own, with
+/// paths relative to `dir`, the chapter's directory.
+///
+/// A file that's missing or can't be read is left to the links preprocessor
+/// to report.
+pub(crate) fn with_frontmatter(content: &str, dir: &Path, config: &Config) -> Vec<PathBuf> {
+    let mut included = vec![];
+    for caps in DIRECTIVE.captures_iter(content) {
+        if caps.name("escape").is_some() {
+            continue;
+        }
+        let path = PathBuf::from(&caps["path"]);
+        let text = match fs::read_to_string(dir.join(&path)) {
+            Ok(text) => text,
+            Err(error) => {
+                debug!("not checking {} for frontmatter: {}", path.display(), error);
+                continue;
+            }
+        };
+        if !parse::block_ranges(&text, config).is_empty() {
+            included.push(path);
+        }
+    }
+    included
+}
@@ -0,0 +1,80 @@
+//! Set a book up to use the preprocessor.
+use log::info;
+use mdbook::errors::Error;
+use std::fs;
+use std::path::Path;
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+/// Stylesheet for the tables, banners and navigation the preprocessor adds.
+const STYLESHEET: &str = include_str!("../assets/frontmatter.css");
+
+/// Where the stylesheet is written, relative to the book root.
+const STYLESHEET_FILE: &str = "frontmatter.css";
+
+/// Add the `[preprocessor.<name>]` table to the `book.toml` of the book at
+/// `root`, write the stylesheet next to it and list it in the html output's
+/// `additional-css`.
+///
+/// Anything already there is left alone, so it's safe to run again.
+pub(crate) fn run(root: &Path, name: &str) -> Result<(), Error> {
+    let book_toml = root.join("book.toml");
+    let text = fs::read_to_string(&book_toml)
+        .map_err(|e| Error::msg(format!("unable to read {}: {}", book_toml.display(), e)))?;
+    let mut document: DocumentMut = text
+        .parse()
+        .map_err(|e| Error::msg(format!("invalid {}: {}", book_toml.display(), e)))?;
+
+    let preprocessors = table(document.as_table_mut(), "preprocessor")?;
+    if !preprocessors.contains_key(name) {
+        let mut preprocessor = Table::new();
+        preprocessor["command"] = value(env!("CARGO_PKG_NAME"));
+        // so chapters are read with their includes expanded
+        preprocessor["after"] = value(Array::from_iter(["links"]));
+        preprocessors.insert(name, Item::Table(preprocessor));
+        info!("adding [preprocessor.{}] to book.toml", name);
+    }
+
+    let html = table(table(document.as_table_mut(), "output")?, "html")?;
+    let additional_css = html
+        .entry("additional-css")
+        .or_insert_with(|| value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| Error::msg("output.html.additional-css must be an array"))?;
+    if !additional_css
+        .iter()
+        .any(|css| css.as_str() == Some(STYLESHEET_FILE))
+    {
+        additional_css.push(STYLESHEET_FILE);
+        info!("adding {} to output.html.additional-css", STYLESHEET_FILE);
+    }
+
+    let updated = document.to_string();
+    if updated != text {
+        fs::write(&book_toml, updated)
+            .map_err(|e| Error::msg(format!("unable to write {}: {}", book_toml.display(), e)))?;
+    }
+
+    let stylesheet = root.join(STYLESHEET_FILE);
+    if stylesheet.exists() {
+        info!("leaving the existing {} as is", stylesheet.display());
+    } else {
+        info!("writing {}", stylesheet.display());
+        fs::write(&stylesheet, STYLESHEET)
+            .map_err(|e| Error::msg(format!("unable to write {}: {}", stylesheet.display(), e)))?;
+    }
+    Ok(())
+}
+
+/// The table at `key` in `parent`, added as a dotted `[parent.key]` header
+/// if it's missing.
+fn table<'a>(parent: &'a mut Table, key: &str) -> Result<&'a mut Table, Error> {
+    parent
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .ok_or_else(|| Error::msg(format!("{} in book.toml must be a table", key)))
+}
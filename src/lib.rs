@@ -1,13 +1,77 @@
 //! Preprocess key/values in-between "+++" as frontmatter.
-use mdbook::book::Book;
+mod banner;
+mod cache;
+mod cascade;
+mod catalog;
+mod computed;
+mod config;
+mod date;
+mod diff;
+mod edit;
+mod error;
+mod export;
+mod feed;
+mod filter;
+mod gettext;
+mod git;
+mod head;
+mod i18n;
+mod include;
+mod install;
+mod linkify;
+mod listing;
+mod marker;
+mod migrate;
+mod order;
+mod output;
+mod parse;
+mod plain;
+mod profile;
+mod protocol;
+mod query;
+mod redirect;
+mod related;
+mod schema;
+mod search;
+mod series;
+mod sitemap;
+mod summary;
+mod table;
+mod taxonomy;
+mod template;
+mod validate;
+mod value;
+mod wrapper;
+
+pub use config::{
+    ChapterOrder, Config, DateDisplay, DuplicateKeys, EmailObfuscation, ExpiredAction, LinkifyRule,
+    Mode, OnError, RendererOutput,
+};
+pub use export::ExportFormat;
+pub use gettext::GettextMode;
+pub use migrate::FrontmatterStyle;
+pub use profile::Profile;
+
+use cache::Cache;
+use cascade::Cascade;
+use catalog::Catalog;
+use chrono::NaiveDateTime;
+use error::FrontmatterError;
+use head::HeadTags;
+use linkify::Linkifier;
+use log::{debug, warn};
+use mdbook::book::{Book, Chapter, SectionNumber};
 use mdbook::errors::Error;
-use mdbook::preprocess::{CmdPreprocessor, Preprocessor, PreprocessorContext};
-use mdbook::BookItem;
-use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
-use pulldown_cmark_to_cmark::cmark;
-use regex::{Captures, Regex};
-use semver::{Version, VersionReq};
+use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use mdbook::{BookItem, MDBook};
+use rayon::prelude::*;
+use redirect::Redirects;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use series::Series;
+use std::collections::HashMap;
 use std::io;
+use std::path::{Path, PathBuf};
 
 #[derive(Default)]
 pub struct FrontmatterPreprocessor;
@@ -18,162 +82,1161 @@ impl FrontmatterPreprocessor {
     /// This method calls the impl `run` method for [Self] to edit content
     /// and return the processed [Book] to stdout.
     pub fn handle_preprocessing(&self) -> Result<(), Error> {
-        let (ctx, book) = CmdPreprocessor::parse_input(io::stdin())?;
+        self.process(io::stdin(), io::stdout())
+    }
 
-        let book_version = Version::parse(&ctx.mdbook_version)?;
-        let version_req = VersionReq::parse(mdbook::MDBOOK_VERSION)?;
+    /// Same as [Self::handle_preprocessing], reading the `[context, book]`
+    /// payload mdbook sends from `input` and writing the book to `output`,
+    /// e.g. to replay a payload saved with `MDBOOK_FRONTMATTER_CAPTURE`.
+    pub fn process(&self, mut input: impl io::Read, output: impl io::Write) -> Result<(), Error> {
+        let mut payload = String::new();
+        input.read_to_string(&mut payload)?;
+        if let Ok(capture) = std::env::var("MDBOOK_FRONTMATTER_CAPTURE") {
+            std::fs::write(&capture, &payload)
+                .map_err(|e| Error::msg(format!("unable to write {}: {}", capture, e)))?;
+        }
+        let (ctx, book, wire) = protocol::read(&payload)?;
 
-        if !version_req.matches(&book_version) {
+        let book_version = Version::parse(&ctx.mdbook_version)?;
+        if !wire.supports(&book_version) {
             // attempt to log error
-            eprintln!(
-                "Warning: The {} plugin was built against version {} of mdbook, \
-                 but we're being called from version {}",
+            warn!(
+                "The {} plugin doesn't know the book layout of mdbook {}, \
+                 the book may not be processed correctly",
                 self.name(),
-                mdbook::MDBOOK_VERSION,
                 ctx.mdbook_version
             );
         }
 
+        // a dry run shows what would change and passes the book on as is
+        if Config::from_context(&ctx, self.name())?.dry_run {
+            let processed_book = self.run(&ctx, book.clone())?;
+            eprint!("{}", diff::book(&book, &processed_book));
+            return protocol::write(&book, wire, output);
+        }
+
         // process book and return frontmatter
         let processed_book = self.run(&ctx, book)?;
-        serde_json::to_writer(io::stdout(), &processed_book)?;
+        protocol::write(&processed_book, wire, output)?;
         Ok(())
     }
-}
 
-impl Preprocessor for FrontmatterPreprocessor {
-    fn name(&self) -> &str {
-        "frontmatter-preprocessor"
+    /// A diff of what an html build of the book at `root` would change in
+    /// each chapter, without writing anything.
+    ///
+    /// Only this preprocessor runs, so `{{#include}}` and the like are left
+    /// as written.
+    pub fn diff(&self, root: &Path) -> Result<String, Error> {
+        let mut md = MDBook::load(root)?;
+        md.config
+            .set(format!("preprocessor.{}.dry_run", self.name()), true)?;
+        let ctx: PreprocessorContext = serde_json::from_value(serde_json::json!({
+            "root": md.root,
+            "config": md.config,
+            "renderer": "html",
+            "mdbook_version": mdbook::MDBOOK_VERSION,
+        }))?;
+        let processed_book = self.run(&ctx, md.book.clone())?;
+        Ok(diff::book(&md.book, &processed_book))
     }
 
-    fn run(&self, _ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
-        // NOTE: "---" is interpreted as Header, so use "+++"
-        let frontmatter_delimiter = CowStr::Borrowed("+++");
+    /// Export the frontmatter of the book at `root`, as data or a flat table.
+    ///
+    /// The chapters are the ones an html build would include.
+    pub fn export(&self, root: &Path, format: ExportFormat) -> Result<String, Error> {
+        export::render(&self.catalog(root)?, format)
+    }
 
-        // loop through each book item to parse chapters
+    /// Export the chapters of the book at `root` that match `query`, e.g.
+    /// `status == "Draft"`.
+    pub fn query(&self, root: &Path, query: &str, format: ExportFormat) -> Result<String, Error> {
+        let query = query::Query::parse(query)?;
+        let mut catalog = self.catalog(root)?;
+        catalog.entries.retain(|entry| query.matches(entry));
+        export::render(&catalog, format)
+    }
+
+    /// Rewrite the frontmatter at the top of every markdown file in the `src`
+    /// directory of the book at `root` from the `from` style to `to`.
+    ///
+    /// Returns the rewritten files, or with `dry_run` a diff of what would
+    /// change without writing anything.
+    pub fn migrate(
+        &self,
+        root: &Path,
+        from: FrontmatterStyle,
+        to: FrontmatterStyle,
+        dry_run: bool,
+    ) -> Result<String, Error> {
+        let config = mdbook::Config::from_disk(root.join("book.toml"))?;
+        migrate::run(&root.join(&config.book.src), from, to, dry_run)
+    }
+
+    /// Set up the book at `root` to use the preprocessor: its
+    /// `book.toml` table and the default stylesheet.
+    pub fn install(&self, root: &Path) -> Result<(), Error> {
+        install::run(root, self.name())
+    }
+
+    /// Process `book` as of `now`, which decides what's scheduled, expired
+    /// and cached for the day.
+    fn run_at(
+        &self,
+        ctx: &PreprocessorContext,
+        mut book: Book,
+        now: NaiveDateTime,
+    ) -> Result<Book, Error> {
+        let config = Config::from_context(ctx, self.name())?;
+        let linkifier = Linkifier::new(&config)?;
+
+        // message extraction needs the source as written, only annotated
+        if ctx.renderer == gettext::RENDERER {
+            book.for_each_mut(|item| {
+                if let BookItem::Chapter(chapter) = item {
+                    let ranges = parse::block_ranges(&chapter.content, &config);
+                    chapter.content =
+                        gettext::mark_frontmatter(&chapter.content, &ranges, config.gettext);
+                }
+            });
+            return Ok(book);
+        }
+
+        let src_dir = ctx.root.join(&ctx.config.book.src);
+        let (cascade, catalog) = collect(&config, &mut book, &ctx.root, &src_dir, now)?;
+
+        // other backends get the chapters as written, with the blocks removed
+        // or as plain lists
+        let output = config.output_for(&ctx.renderer);
+        if output != RendererOutput::Table {
+            let metadata = output == RendererOutput::List;
+            book.for_each_mut(|item| {
+                if let BookItem::Chapter(chapter) = item {
+                    let defaults = cascade.defaults_for(chapter);
+                    let mut frontmatter = parse::read_frontmatter(&chapter.content, &config);
+                    cascade::merge(&mut frontmatter, &defaults);
+                    let origin = chapter.name.clone();
+                    let content = template::substitute(
+                        &template::conditionals(&chapter.content, &frontmatter, &origin),
+                        &frontmatter,
+                        &origin,
+                    );
+                    chapter.content = plain::render(&content, &config, &defaults, metadata);
+                    if config.metadata_comment {
+                        chapter
+                            .content
+                            .insert_str(0, &marker::comment(&frontmatter));
+                    }
+                }
+            });
+            return Ok(book);
+        }
+
+        let part_heads = cascade::part_heads(&book.sections);
+        let series = Series::new(&catalog);
+        let mut head_tags = HeadTags::new(ctx.config.book.title.clone(), &book);
+        let mut redirects = Redirects::default();
+        let mut report = validate::Report::new(&config, &ctx.root)?;
+        let mut summary = summary::Summary::default();
+        // callouts use mdbook-admonish's syntax when it runs too
+        let admonish = ctx.config.get("preprocessor.admonish").is_some();
+
+        // the context can't be shared between threads
+        let html = ctx.renderer == "html";
+        let book_src = &ctx.config.book.src;
+
+        let mut cache = if config.cache {
+            Cache::load(ctx.root.join(&config.cache_path))
+        } else {
+            Cache::default()
+        };
+        let book_key = cache::key((
+            env!("CARGO_PKG_VERSION"),
+            format!("{:?}", ctx.config),
+            &ctx.renderer,
+            now.date(),
+            &catalog.entries,
+        ));
+
+        // chapters are rendered on their own, in parallel, and what the
+        // whole book collects is gathered afterwards in book order
+        let render = |chapter: &Chapter| -> Result<Option<(String, Rendered)>, FrontmatterError> {
+            // draft SUMMARY entries have no file, so nothing to parse
+            let Some(path) = chapter.path.clone() else {
+                if !chapter.content.is_empty() {
+                    warn!("skipping {:?}, it isn't backed by a file", chapter.name);
+                }
+                return Ok(None);
+            };
+
+            // parts have no page, so their first chapter shows their keys
+            let defaults = cascade.defaults_for(chapter);
+            let part = cascade.part_of(chapter).filter(|_| {
+                chapter
+                    .source_path
+                    .as_ref()
+                    .is_some_and(|path| part_heads.contains(path))
+            });
+            let key = cache::key((
+                &book_key,
+                &chapter.content,
+                &chapter.name,
+                &path,
+                &defaults,
+                part,
+            ));
+            if let Some(rendered) = cache.get(&key) {
+                return Ok(Some((key, rendered)));
+            }
+
+            let origin = path.display().to_string();
+            let chapter_dir = src_dir.join(&path);
+            let chapter_dir = chapter_dir.parent().unwrap_or(&src_dir);
+            for included in include::with_frontmatter(&chapter.content, chapter_dir, &config) {
+                warn!(
+                    "{}: {} has frontmatter of its own, which is left as written unless \
+                     the preprocessor runs after links (after = [\"links\"])",
+                    origin,
+                    included.display()
+                );
+            }
+            // strict builds don't publish a block that was never closed
+            if config.mode == Mode::Strict {
+                if let Some(line) = parse::unclosed_block(&chapter.content, &config) {
+                    return Err(FrontmatterError::Unclosed { line });
+                }
+            }
+            let lines = parse::key_lines(&chapter.content, &config);
+            let diagnostics = parse::diagnostics(&chapter.content, &config);
+
+            // `{{#if fm.key}}` blocks and `{{fm.key}}` references go
+            // before anything is rendered
+            let mut frontmatter = parse::read_frontmatter(&chapter.content, &config);
+            let written = frontmatter.clone();
+            if frontmatter.is_empty() {
+                debug!("{}: no frontmatter", origin);
+            } else {
+                debug!(
+                    "{}: frontmatter keys {}",
+                    origin,
+                    frontmatter
+                        .iter()
+                        .map(|(key, _)| key.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            let mut missing_defaults = cascade::merge(&mut frontmatter, &defaults);
+            if !missing_defaults.is_empty() {
+                debug!("{}: {} keys from defaults", origin, missing_defaults.len());
+            }
+            let content = template::substitute(
+                &template::conditionals(&chapter.content, &frontmatter, &origin),
+                &frontmatter,
+                &origin,
+            );
+            // computed rows show like defaults, unless the chapter sets them
+            missing_defaults.extend(cascade::merge(
+                &mut frontmatter,
+                &computed::fields(&content, &config),
+            ));
+            let mut missing_defaults = Some(missing_defaults);
+
+            // every key/value found in the chapter
+            let mut chapter_frontmatter = vec![];
+            let mut tables = 0;
+
+            // swap each frontmatter block for its table, the first one
+            // showing the defaults too
+            let mut blocks = 0;
+            let mut formatted_content =
+                parse::try_splice_frontmatter(&content, &config, |mut frontmatter, section| {
+                    tables += 1;
+                    if let Some(section) = section {
+                        return table::create_section_table(
+                            frontmatter,
+                            &config,
+                            &linkifier,
+                            &path,
+                            section,
+                        );
+                    }
+                    frontmatter.extend(missing_defaults.take().unwrap_or_default());
+                    chapter_frontmatter.extend(frontmatter.iter().cloned());
+                    // with `anywhere`, the rows of the blocks after the first
+                    // are numbered like sections' so their ids stay apart
+                    blocks += 1;
+                    if blocks > 1 {
+                        return table::create_section_table(
+                            frontmatter,
+                            &config,
+                            &linkifier,
+                            &path,
+                            blocks - 1,
+                        );
+                    }
+                    table::create_html_table(frontmatter, &config, &linkifier, &path)
+                })?;
+            let mut chapter_frontmatter =
+                parse::resolve_duplicates(chapter_frontmatter, config.duplicate_keys);
+            if let Some(missing_defaults) = missing_defaults.filter(|d| !d.is_empty()) {
+                chapter_frontmatter.extend(missing_defaults.iter().cloned());
+                if config.default_table {
+                    let table =
+                        table::create_html_table(missing_defaults, &config, &linkifier, &path)?;
+                    formatted_content.insert_str(0, &table);
+                    tables += 1;
+                }
+            }
+            debug!("{}: rendered {} tables", origin, tables);
+
+            if let Some((title, keys)) = part {
+                let overview =
+                    table::create_part_overview(title, keys.to_vec(), &config, &linkifier, &path)?;
+                formatted_content.insert_str(0, &overview);
+            }
+
+            // use the frontmatter title for sidebar, page title and search
+            let name = chapter_frontmatter
+                .iter()
+                .find(|(key, value)| key == "title" && !value.is_empty())
+                .map(|(_, title)| title.clone())
+                .filter(|_| config.override_title);
+
+            // scheduled chapters only get this far in preview builds
+            let mut banners = String::new();
+            if config.search_terms && html {
+                if let Some(terms) = search::terms_block(&chapter_frontmatter, &config) {
+                    banners.push_str(&terms);
+                }
+            }
+            if let Some(date) = filter::publish_date(&chapter_frontmatter) {
+                if date > now {
+                    banners.push_str(&banner::banner(
+                        "unpublished",
+                        &format!("Unpublished: scheduled for {}.", date.format("%Y-%m-%d")),
+                    ));
+                }
+            }
+            if let Some((key, date)) = filter::expiry(&chapter_frontmatter) {
+                if date <= now {
+                    let reason = if key == "expires" {
+                        "It expired on"
+                    } else {
+                        "Its review was due on"
+                    };
+                    banners.push_str(&banner::banner(
+                        "outdated",
+                        &format!(
+                            "This content may be outdated. {} {}.",
+                            reason,
+                            date.format("%Y-%m-%d")
+                        ),
+                    ));
+                }
+            }
+
+            if config.deprecation_banner && filter::deprecated(&chapter_frontmatter) {
+                let message = template::substitute(
+                    &template::conditionals(
+                        &config.deprecation_message,
+                        &chapter_frontmatter,
+                        "deprecation_message",
+                    ),
+                    &chapter_frontmatter,
+                    "deprecation_message",
+                );
+                banners.push_str(&banner::banner("deprecated", &message));
+            }
+
+            for (key, value) in &chapter_frontmatter {
+                if let Some(kind) = config.callouts.get(key).filter(|_| !value.is_empty()) {
+                    banners.push_str(&banner::callout(kind, value, admonish));
+                }
+            }
+
+            if config.category_pages {
+                if let Some(crumbs) = chapter_frontmatter
+                    .iter()
+                    .find(|(key, _)| key == "category")
+                    .and_then(|(_, category)| taxonomy::breadcrumbs(&config, &path, category))
+                {
+                    banners.push_str(&crumbs);
+                }
+            }
+            if let Some(nav) = series.nav(chapter) {
+                banners.push_str(&nav);
+            }
+
+            // replace chapter content with formatted content
+            let mut buf = banners;
+            buf.push_str(&formatted_content);
+
+            if config.edit_link {
+                if let Some(url) = chapter.source_path.as_ref().and_then(|source_path| {
+                    edit::edit_url(&chapter_frontmatter, &config, book_src, source_path)
+                }) {
+                    buf.push_str(&edit::edit_link(&url, &config));
+                }
+            }
+            if config.related {
+                if let Some(related) = related::related(&catalog, chapter, config.related_limit) {
+                    buf.push_str(&related);
+                }
+            }
+            let mut content = wrapper::wrap(buf, &chapter_frontmatter, &config);
+            if config.metadata_comment {
+                content.insert_str(0, &marker::comment(&chapter_frontmatter));
+            }
+            let rendered = Rendered {
+                path,
+                content,
+                name,
+                diagnostics,
+                lines,
+                written,
+                frontmatter: chapter_frontmatter,
+                tables,
+            };
+            Ok(Some((key, rendered)))
+        };
+        let mut chapters = vec![];
+        visit_order(&book.sections, &mut chapters);
+        let rendered: Vec<Result<Option<(String, Rendered)>, FrontmatterError>> = chapters
+            .into_par_iter()
+            .map(|chapter| render(chapter).map_err(|error| error.in_chapter(origin_of(chapter))))
+            .collect();
+
+        let mut fresh = HashMap::new();
+        let mut failed = None;
+        let mut rendered = rendered.into_iter();
         book.for_each_mut(|item| {
-            // only parse chapters
-            if let BookItem::Chapter(chapter) = item {
-                // flag for capturing frontmatter
-                let mut capture = false;
-                let mut frontmatter_collection = vec![];
-                let mut formatted_content = vec![];
-
-                // create markdown parser for events
-                let parser = pulldown_cmark::Parser::new(&chapter.content);
-
-                // loop through events to find frontmatter section based on delimiter
-                for event in parser {
-                    match event {
-                        // handle delimiter
-                        Event::Text(ref text) if text == &frontmatter_delimiter => {
-                            // first time seeing delimiter, this is false
-                            // second time, construct table with captured frontmatter
-                            if capture {
-                                let frontmatter = parse_frontmatter(&frontmatter_collection);
-                                let html_table = create_html_table_events(frontmatter);
-
-                                // concat doesn't work
-                                for event in html_table {
-                                    formatted_content.push(event);
-                                }
-                                frontmatter_collection.clear();
-                            }
-                            // turn capture flag "true"
-                            //
-                            // and don't capture the delimiter event
-                            capture = !capture;
+            let BookItem::Chapter(chapter) = item else {
+                return;
+            };
+            let (key, rendered) = match rendered.next() {
+                Some(Ok(Some(rendered))) => rendered,
+                Some(Ok(None)) | None => return,
+                Some(Err(error)) => return chapter_failed(error, config.on_error, &mut failed),
+            };
+            if cache.enabled() {
+                fresh.insert(key, rendered.clone());
+            }
+            report.check_syntax(&rendered.path, &rendered.diagnostics);
+            report.check_keys(&rendered.path, &rendered.written, &rendered.lines, &config);
+            summary.chapter(&rendered.written, rendered.tables);
+            if let Some(name) = rendered.name {
+                chapter.name = name;
+            }
+            report.check(
+                &rendered.path,
+                &rendered.frontmatter,
+                &rendered.lines,
+                &config,
+            );
+            if let Err(error) = head_tags.collect(chapter, &rendered.frontmatter, &config) {
+                return chapter_failed(
+                    error.in_chapter(origin_of(chapter)),
+                    config.on_error,
+                    &mut failed,
+                );
+            }
+            redirects.collect(chapter, &rendered.frontmatter);
+            chapter.content = rendered.content;
+        });
+        if let Some(error) = failed {
+            return Err(error.into());
+        }
+
+        summary.log(report.len(), config.verbose);
+        report.finish(config.mode)?;
+
+        // generated pages go last, after every chapter was rendered
+        if config.tag_pages {
+            book.sections
+                .extend(taxonomy::tag_pages(&catalog, &config).map(BookItem::Chapter));
+        }
+        if config.author_page {
+            book.sections
+                .extend(taxonomy::author_page(&catalog, &config).map(BookItem::Chapter));
+        }
+        if config.category_pages {
+            book.sections
+                .extend(taxonomy::category_pages(&catalog, &config).map(BookItem::Chapter));
+        }
+        if config.changelog {
+            book.sections
+                .push(BookItem::Chapter(listing::changelog(&catalog, &config)));
+        }
+        if config.document_index {
+            book.sections
+                .push(BookItem::Chapter(listing::document_index(
+                    &catalog, &config,
+                )));
+        }
+
+        // a dry run writes no files
+        if config.dry_run {
+            return Ok(book);
+        }
+        cache.save(fresh)?;
+
+        // only the html renderer has a page head
+        if config.head_tags && ctx.renderer == "html" {
+            let theme_dir = match ctx.config.html_config() {
+                Some(html) => html.theme_dir(&ctx.root),
+                None => ctx.root.join("theme"),
+            };
+            head_tags.write(&theme_dir)?;
+        }
+
+        if ctx.renderer == "html" {
+            redirects.write(&src_dir)?;
+
+            if config.rss || config.atom {
+                match &config.base_url {
+                    Some(base_url) => {
+                        let channel = feed::Channel {
+                            title: ctx.config.book.title.as_deref().unwrap_or_default(),
+                            description: ctx.config.book.description.as_deref(),
+                            base_url,
+                            authors: &ctx.config.book.authors,
+                        };
+                        if config.rss {
+                            feed::write_rss(&catalog, &config, &channel, &src_dir)?;
                         }
-                        // capture content within frontmatter delimiters
-                        Event::Text(content) if capture => {
-                            frontmatter_collection.push(content.to_string())
+                        if config.atom {
+                            feed::write_atom(&catalog, &config, &channel, &src_dir)?;
                         }
-                        // avoid capturing "SoftBreak", etc. in frontmatter
-                        _ if !capture => formatted_content.push(event),
-                        // ignore "SoftBreak"s in frontmatter section
-                        _ => (),
                     }
+                    None => warn!("not writing a feed without a base_url"),
                 }
+            }
 
-                // replace chapter content with formatted content
-                let mut buf = String::with_capacity(chapter.content.len());
-                chapter.content = cmark(formatted_content.iter(), &mut buf)
-                    .map(|_| buf)
-                    .expect("Markdown serialization failed")
+            if config.metadata_json {
+                export::write_json(&catalog, &config, &src_dir)?;
             }
-        });
+            if config.chapter_json {
+                export::write_sidecars(&catalog, &src_dir)?;
+            }
+            if config.csv_export {
+                export::write_table(&catalog, &config, &src_dir)?;
+            }
+
+            if config.sitemap {
+                match &config.base_url {
+                    Some(base_url) => sitemap::write(&catalog, &config, base_url, &src_dir)?,
+                    None => warn!("not writing a sitemap without a base_url"),
+                }
+            }
+        }
 
         Ok(book)
     }
+
+    /// Every chapter's frontmatter in the book at `root`, loaded outside of a
+    /// build.
+    fn catalog(&self, root: &Path) -> Result<Catalog, Error> {
+        let mut md = MDBook::load(root)?;
+        let config = Config::from_book_config(&md.config, self.name())?;
+        let src_dir = md.root.join(&md.config.book.src);
+        let now = chrono::Local::now().naive_local();
+        let (_, catalog) = collect(&config, &mut md.book, &md.root, &src_dir, now)?;
+        Ok(catalog)
+    }
 }
 
-/// Create key/values for frontmatter by splitting ":" and trimming whitespace.
-///
-/// Use a `Vec` so the order is preserved.
-fn parse_frontmatter(frontmatter_text: &[String]) -> Vec<(String, String)> {
-    frontmatter_text
-        .iter()
-        .filter_map(|line| {
-            // separate by colon + space
-            let parts: Vec<_> = line.splitn(2, ':').collect();
-
-            if parts.len() == 2 {
-                Some((parts[0].trim().to_string(), parts[1].trim().to_string()))
-            } else {
-                None
-            }
-        })
-        .collect()
+impl Preprocessor for FrontmatterPreprocessor {
+    fn name(&self) -> &str {
+        "frontmatter"
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, book: Book) -> Result<Book, Error> {
+        self.run_at(ctx, book, chrono::Local::now().naive_local())
+    }
+
+    fn supports_renderer(&self, renderer: &str) -> bool {
+        // supports HTML renderer, text backends and epub, and xgettext for
+        // translation workflows; mdbook doesn't ask when book.toml lists
+        // the renderers itself
+        matches!(
+            renderer,
+            "html"
+                | plain::RENDERER
+                | plain::EPUB_RENDERER
+                | plain::LINKCHECK_RENDERER
+                | gettext::RENDERER
+        )
+    }
+}
+
+/// How a chapter is named in errors: its path, or for a draft its name.
+fn origin_of(chapter: &Chapter) -> String {
+    chapter
+        .path
+        .as_ref()
+        .map_or_else(|| chapter.name.clone(), |path| path.display().to_string())
 }
 
-/// Create owned events for table html.
-///
-/// The events are created for use with pulldown cmark.
-///
-/// There may be a better way to do this, but this seems sturdy.
-fn create_html_table_events<'a>(frontmatter: Vec<(String, String)>) -> Vec<Event<'a>> {
-    // create events for cmark
-    let mut events = vec![];
-    // start tag
-    events.push(Event::Start(Tag::HtmlBlock));
-    // create table
-    events.push(Event::Html(CowStr::Boxed(
-        "<table class=\"preamble\">\n".into(),
-    )));
-    // loop through frontmatter to create table rows
-    for (key, mut value) in frontmatter {
-        // create links for github/email
-        if key == "author" {
-            value = linkify_text(&value)
-        }
-
-        events.push(Event::Html(CowStr::Boxed(
-            format!("<tr><th>{}</td><td>{}</td></tr>\n", key, value).into(),
-        )));
-    }
-    // close table
-    events.push(Event::Html(CowStr::Boxed("</table>\n".into())));
-    // end tag
-    events.push(Event::End(TagEnd::HtmlBlock));
-    events
+/// Handle a chapter that couldn't be rendered as `on_error` says: keep the
+/// first error to fail the build with, or warn and leave the chapter as it is.
+fn chapter_failed(
+    error: FrontmatterError,
+    on_error: OnError,
+    failed: &mut Option<FrontmatterError>,
+) {
+    match on_error {
+        OnError::Fail => {
+            failed.get_or_insert(error);
+        }
+        OnError::Warn => warn!("{}, leaving the chapter as written", error),
+    }
 }
 
-/// Create anchor tags for github usernames and emails inside frontmatter.
-fn linkify_text(text: &str) -> String {
-    // Regex to find GitHub usernames and emails
-    let github_regex = Regex::new(r"\(@([a-zA-Z0-9_]+)\)").expect("github regex");
-    let email_regex =
-        Regex::new(r"\(([a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)\)").expect("email regex");
+/// Chapters in the order `Book::for_each_mut` visits them, sub-chapters
+/// before their parent.
+fn visit_order<'a>(items: &'a [BookItem], chapters: &mut Vec<&'a Chapter>) {
+    for item in items {
+        if let BookItem::Chapter(chapter) = item {
+            visit_order(&chapter.sub_items, chapters);
+            chapters.push(chapter);
+        }
+    }
+}
 
-    // Replace GitHub usernames with links
-    let text = github_regex.replace_all(text, |caps: &Captures| {
-        format!("(<a href=\"https://github.com/{0}\">@{0}</a>)", &caps[1])
-    });
+/// A chapter rendered on its own, with what the whole book collects from it.
+#[derive(Clone, Serialize, Deserialize)]
+struct Rendered {
+    path: PathBuf,
+    content: String,
+    /// The frontmatter title, when it replaces the chapter's name.
+    name: Option<String>,
+    diagnostics: Vec<parse::Diagnostic>,
+    /// Where the chapter wrote each key.
+    lines: Vec<(String, usize)>,
+    /// The keys the chapter wrote itself.
+    written: Vec<(String, String)>,
+    /// Every key the chapter has, defaults and computed rows included.
+    frontmatter: Vec<(String, String)>,
+    tables: usize,
+}
 
-    // Replace emails with mailto links
-    let text = email_regex.replace_all(&text, |caps: &Captures| {
-        format!("(<a href=\"mailto:{}\">{}</a>)", &caps[1], &caps[1])
+/// Drop the chapters this build leaves out, put the rest in order, and collect
+/// every chapter's frontmatter.
+fn collect(
+    config: &Config,
+    book: &mut Book,
+    root: &Path,
+    src_dir: &Path,
+    now: NaiveDateTime,
+) -> Result<(Cascade, Catalog), Error> {
+    // drop drafts, scheduled, expired and other audiences' chapters first
+    // so nothing else sees them
+    let cascade = Cascade::new(config, book, root, src_dir)?;
+    filter::retain_chapters(&mut book.sections, &mut |chapter| {
+        let mut frontmatter = parse::read_frontmatter(&chapter.content, config);
+        cascade::merge(&mut frontmatter, &cascade.defaults_for(chapter));
+        let draft = frontmatter
+            .iter()
+            .any(|(key, value)| key == "draft" && value::is_true(value));
+        let scheduled = filter::publish_date(&frontmatter).is_some_and(|date| date > now);
+        let expired = filter::expiry(&frontmatter).is_some_and(|(_, date)| date <= now);
+
+        (!draft || config.include_drafts)
+            && (!scheduled || config.preview)
+            && (!expired || config.expired != ExpiredAction::Remove)
+            && filter::for_audience(&frontmatter, &config.audience)
     });
 
-    text.to_string()
+    // keys as the chapter ends up with them, defaults included
+    let frontmatter_of = |chapter: &Chapter| {
+        let mut frontmatter = parse::read_frontmatter(&chapter.content, config);
+        cascade::merge(&mut frontmatter, &cascade.defaults_for(chapter));
+        frontmatter
+    };
+    let value = |chapter: &Chapter, key: &str| {
+        frontmatter_of(chapter)
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    };
+    let weighted = config.weights && order::sort_by_weight(&mut book.sections, value);
+    let sorted = order::sort_parts(&mut book.sections, &config.sort_parts, value);
+    let unnumbered = order::unnumber(&mut book.sections, &value);
+    if weighted || sorted || unnumbered {
+        order::renumber(&mut book.sections, &SectionNumber::default(), &mut 0);
+    }
+
+    let catalog = Catalog::new(book, frontmatter_of);
+    Ok((cascade, catalog))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::{Mutex, Once};
+
+    /// Warnings logged by every test, which tell theirs apart by chapter.
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Capture;
+
+    impl log::Log for Capture {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn warnings_about(chapter: &str) -> Vec<String> {
+        WARNINGS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|warning| warning.contains(chapter))
+            .cloned()
+            .collect()
+    }
+
+    /// Run an html build of `sections` in an empty book, writing no files.
+    fn process(sections: Vec<BookItem>) -> Book {
+        try_process(sections, &[]).unwrap()
+    }
+
+    /// Same as [process], with `settings` under `[preprocessor.frontmatter]`.
+    fn try_process(
+        sections: Vec<BookItem>,
+        settings: &[(&str, toml::Value)],
+    ) -> Result<Book, Error> {
+        let root = tempfile::tempdir().unwrap();
+        try_process_in(root.path(), sections, settings)
+    }
+
+    /// Same as [try_process], for the book at `root`.
+    fn try_process_in(
+        root: &Path,
+        sections: Vec<BookItem>,
+        settings: &[(&str, toml::Value)],
+    ) -> Result<Book, Error> {
+        let mut book = Book::new();
+        book.sections = sections;
+        FrontmatterPreprocessor.run(&context(root, settings), book)
+    }
+
+    /// The context of an html build of the book at `root`, with `settings`
+    /// under `[preprocessor.frontmatter]`.
+    fn context(root: &Path, settings: &[(&str, toml::Value)]) -> PreprocessorContext {
+        static LOGGER: Once = Once::new();
+        LOGGER.call_once(|| {
+            log::set_logger(&Capture).expect("no other logger");
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+
+        let mut config = mdbook::Config::default();
+        config
+            .set("preprocessor.frontmatter.dry_run", true)
+            .unwrap();
+        for (key, value) in settings {
+            config
+                .set(format!("preprocessor.frontmatter.{}", key), value)
+                .unwrap();
+        }
+        serde_json::from_value(serde_json::json!({
+            "root": root,
+            "config": config,
+            "renderer": "html",
+            "mdbook_version": mdbook::MDBOOK_VERSION,
+        }))
+        .unwrap()
+    }
+
+    fn chapter(name: &str, content: &str, number: Option<u32>) -> BookItem {
+        let path = format!("{}.md", name.to_lowercase());
+        let mut chapter = Chapter::new(name, content.to_string(), path, vec![]);
+        chapter.number = number.map(|n| SectionNumber(vec![n]));
+        BookItem::Chapter(chapter)
+    }
+
+    fn chapters(book: &Book) -> Vec<&Chapter> {
+        book.iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => Some(chapter),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prefix_and_suffix_chapters_are_rendered() {
+        let book = try_process(
+            vec![
+                chapter(
+                    "Preface",
+                    "+++\ntitle: Before\nowner: docs\n+++\n\nHello\n",
+                    None,
+                ),
+                chapter("Guide", "+++\nowner: docs\n+++\n\nGuide\n", Some(1)),
+                BookItem::Separator,
+                chapter("Appendix", "+++\nowner: ops\n+++\n\nLast\n", None),
+            ],
+            &[("override_title", true.into())],
+        )
+        .unwrap();
+        let chapters = chapters(&book);
+        for chapter in &chapters {
+            assert!(chapter.content.contains("<table"), "{}", chapter.content);
+            assert!(!chapter.content.contains("+++"), "{}", chapter.content);
+        }
+        assert_eq!(chapters[0].name, "Before");
+        assert!(chapters[0].content.contains("Hello"));
+        assert!(chapters[2].content.contains("<td>ops</td>"));
+        assert!(warnings_about("preface.md").is_empty());
+        assert!(warnings_about("appendix.md").is_empty());
+    }
+
+    #[test]
+    fn summary_name_is_kept_by_default() {
+        let book = process(vec![chapter(
+            "Named",
+            "+++\ntitle: Renamed\n+++\n",
+            Some(1),
+        )]);
+        assert_eq!(chapters(&book)[0].name, "Named");
+    }
+
+    #[test]
+    fn draft_chapters_are_skipped() {
+        let draft = "+++\nowner: drafts\n+++\n";
+        let mut written = Chapter::new_draft("Written draft", vec![]);
+        written.content = draft.to_string();
+        let book = process(vec![
+            chapter("Intro", "Intro\n", Some(1)),
+            BookItem::PartTitle("Later".to_string()),
+            BookItem::Chapter(Chapter::new_draft("Empty draft", vec![])),
+            BookItem::Chapter(written),
+        ]);
+        let chapters = chapters(&book);
+        assert_eq!(chapters[1].content, "");
+        assert_eq!(chapters[2].content, draft);
+        assert!(warnings_about("Empty draft").is_empty());
+        assert_eq!(
+            warnings_about("Written draft"),
+            ["skipping \"Written draft\", it isn't backed by a file"]
+        );
+    }
+
+    /// Noon on a day of 2024, for builds whose chapters are dated.
+    fn day(month: u32, day: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, month, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    /// Same as [process], as of `now`.
+    fn process_at(
+        sections: Vec<BookItem>,
+        settings: &[(&str, toml::Value)],
+        now: NaiveDateTime,
+    ) -> Book {
+        let root = tempfile::tempdir().unwrap();
+        let mut book = Book::new();
+        book.sections = sections;
+        FrontmatterPreprocessor
+            .run_at(&context(root.path(), settings), book, now)
+            .unwrap()
+    }
+
+    fn names(book: &Book) -> Vec<&str> {
+        chapters(book)
+            .iter()
+            .map(|chapter| chapter.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn draft_keys_drop_chapters_unless_drafts_are_included() {
+        let sections = || {
+            let mut draft = Chapter::new(
+                "Draft",
+                "+++\ndraft: true\n+++\n".into(),
+                "draft.md",
+                vec![],
+            );
+            draft.number = Some(SectionNumber(vec![2]));
+            draft.sub_items = vec![chapter("Child", "Child\n", Some(1))];
+            vec![
+                chapter("Intro", "Intro\n", Some(1)),
+                BookItem::Chapter(draft),
+            ]
+        };
+        assert_eq!(names(&process(sections())), ["Intro"]);
+
+        let book = try_process(sections(), &[("include_drafts", true.into())]).unwrap();
+        assert_eq!(names(&book), ["Intro", "Draft", "Child"]);
+    }
+
+    #[test]
+    fn audience_defaults_to_public() {
+        let sections = || {
+            vec![
+                chapter("Public", "Public\n", Some(1)),
+                chapter("Internal", "+++\naudience: internal\n+++\n", Some(2)),
+                chapter("Everyone", "+++\naudience: all\n+++\n", Some(3)),
+                chapter("Both", "+++\naudience: [Public, internal]\n+++\n", Some(4)),
+            ]
+        };
+        assert_eq!(names(&process(sections())), ["Public", "Everyone", "Both"]);
+
+        let book = try_process(sections(), &[("audience", "internal".into())]).unwrap();
+        assert_eq!(names(&book), ["Public", "Internal", "Everyone", "Both"]);
+    }
+
+    #[test]
+    fn scheduled_chapters_appear_on_their_date() {
+        let sections = || {
+            vec![
+                chapter("Intro", "Intro\n", Some(1)),
+                chapter("Launch", "+++\npublish_date: 2024-06-01\n+++\n", Some(2)),
+            ]
+        };
+        assert_eq!(names(&process_at(sections(), &[], day(5, 1))), ["Intro"]);
+
+        let preview = process_at(sections(), &[("preview", true.into())], day(5, 1));
+        assert!(chapters(&preview)[1]
+            .content
+            .contains("Unpublished: scheduled for 2024-06-01."));
+
+        let published = process_at(sections(), &[], day(6, 1));
+        assert_eq!(names(&published), ["Intro", "Launch"]);
+        assert!(!chapters(&published)[1].content.contains("Unpublished"));
+    }
+
+    #[test]
+    fn expired_chapters_get_a_banner_unless_removed() {
+        let sections = || {
+            vec![
+                chapter("Intro", "Intro\n", Some(1)),
+                chapter("Runbook", "+++\nexpires: 2024-04-01\n+++\n", Some(2)),
+                chapter("Policy", "+++\nreview_by: 2024-04-15\n+++\n", Some(3)),
+            ]
+        };
+        let book = process_at(sections(), &[], day(5, 1));
+        let expired = chapters(&book);
+        assert!(expired[0].content.starts_with("Intro"));
+        assert!(expired[1]
+            .content
+            .contains("This content may be outdated. It expired on 2024-04-01."));
+        assert!(expired[2]
+            .content
+            .contains("This content may be outdated. Its review was due on 2024-04-15."));
+
+        let fresh = process_at(sections(), &[], day(3, 1));
+        assert!(!chapters(&fresh)
+            .iter()
+            .any(|c| c.content.contains("outdated")));
+
+        let removed = process_at(sections(), &[("expired", "remove".into())], day(5, 1));
+        assert_eq!(names(&removed), ["Intro"]);
+    }
+
+    /// A chapter whose block is never closed, which strict builds can't
+    /// render.
+    fn unclosed(name: &str) -> BookItem {
+        chapter(name, "+++\nowner: docs\n\nText\n", Some(1))
+    }
+
+    #[test]
+    fn failed_chapter_fails_the_build() {
+        let error =
+            try_process(vec![unclosed("Broken")], &[("mode", "strict".into())]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broken.md: rendering failed: frontmatter block on line 1 is never closed"
+        );
+    }
+
+    #[test]
+    fn failed_chapter_is_left_as_written_with_warn() {
+        let book = try_process(
+            vec![
+                unclosed("Unclosed"),
+                chapter("Fine", "+++\nowner: docs\n+++\n", Some(2)),
+            ],
+            &[("mode", "strict".into()), ("on_error", "warn".into())],
+        )
+        .unwrap();
+        let chapters = chapters(&book);
+        assert_eq!(chapters[0].content, "+++\nowner: docs\n\nText\n");
+        assert!(chapters[1].content.contains("<table"));
+        assert_eq!(
+            warnings_about("unclosed.md"),
+            [
+                "unclosed.md: rendering failed: frontmatter block on line 1 is never closed, \
+              leaving the chapter as written"
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_block_is_a_warning_when_lenient() {
+        let book = process(vec![unclosed("Lenient")]);
+        assert_eq!(chapters(&book)[0].content, "+++\nowner: docs\n\nText\n");
+        let warnings = warnings_about("lenient.md");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("is never closed"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn unreadable_includes_are_left_to_links() {
+        let root = tempfile::tempdir().unwrap();
+        let src = root.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("latin1.svg"), b"<svg>caf\xe9</svg>").unwrap();
+        let book = try_process_in(
+            root.path(),
+            vec![chapter(
+                "Includes",
+                "+++\nowner: docs\n+++\n\n{{#include latin1.svg}}\n{{#include dir}}\n",
+                Some(1),
+            )],
+            &[],
+        )
+        .unwrap();
+        assert!(chapters(&book)[0].content.contains("<td>docs</td>"));
+        assert!(warnings_about("includes.md").is_empty());
+    }
+
+    #[test]
+    fn later_blocks_get_numbered_row_ids() {
+        let book = try_process(
+            vec![chapter(
+                "Blocks",
+                "+++\nowner: docs\n+++\n\nText\n\n+++\nowner: ops\n+++\n",
+                Some(1),
+            )],
+            &[("anywhere", true.into())],
+        )
+        .unwrap();
+        let content = &chapters(&book)[0].content;
+        assert!(content.contains("<tr id=\"fm-owner\">"), "{}", content);
+        assert!(content.contains("<tr id=\"fm-1-owner\">"), "{}", content);
+    }
+
+    #[test]
+    fn directory_defaults_are_read_from_defaults_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("cascade-defaults");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(cascade::DIRECTORY_FILE), "team = \"Platform\"\n").unwrap();
+        let book = try_process_in(
+            root.path(),
+            vec![chapter("Team", "+++\nowner: docs\n+++\n", Some(1))],
+            &[("defaults_dir", "cascade-defaults".into())],
+        )
+        .unwrap();
+        let content = &chapters(&book)[0].content;
+        assert!(content.contains("<td>Platform</td>"), "{}", content);
+    }
+
+    #[test]
+    fn part_keys_are_read_next_to_book_toml() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(
+            root.path().join(cascade::PARTS_FILE),
+            "[\"Operations\"]\nowner = \"SRE\"\n",
+        )
+        .unwrap();
+        let book = try_process_in(
+            root.path(),
+            vec![
+                BookItem::PartTitle("Operations".to_string()),
+                chapter("Runbook", "+++\nstatus: draft\n+++\n", Some(1)),
+            ],
+            &[("part_cascade", true.into())],
+        )
+        .unwrap();
+        let content = &chapters(&book)[0].content;
+        assert!(content.contains("SRE"), "{}", content);
+    }
+
+    #[test]
+    fn metadata_json_is_written_into_src() {
+        let root = tempfile::tempdir().unwrap();
+        try_process_in(
+            root.path(),
+            vec![chapter("Exported", "+++\nowner: docs\n+++\n", Some(1))],
+            &[("dry_run", false.into()), ("metadata_json", true.into())],
+        )
+        .unwrap();
+        let json = fs::read_to_string(root.path().join("src/frontmatter.json")).unwrap();
+        assert!(json.contains("\"owner\": \"docs\""), "{}", json);
+    }
+
+    #[test]
+    fn sidecars_are_written_next_to_their_chapter() {
+        let root = tempfile::tempdir().unwrap();
+        try_process_in(
+            root.path(),
+            vec![chapter("Sidecar", "+++\nowner: docs\n+++\n", Some(1))],
+            &[("dry_run", false.into()), ("chapter_json", true.into())],
+        )
+        .unwrap();
+        let json = fs::read_to_string(root.path().join("src/sidecar.frontmatter.json")).unwrap();
+        assert!(json.contains("\"owner\": \"docs\""), "{}", json);
+    }
+
+    #[test]
+    fn cached_chapters_are_reused_until_an_input_changes() {
+        let root = tempfile::tempdir().unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let tomorrow = today + chrono::Duration::days(1);
+        let settings = [("dry_run", false.into()), ("cache", true.into())];
+        let captioned = [
+            ("dry_run", false.into()),
+            ("cache", true.into()),
+            ("table_caption", "Keys".into()),
+        ];
+
+        // a build with `other` as the other chapter's owner, telling whether
+        // the first chapter came from the cache
+        let cache = root.path().join(".frontmatter-cache.json");
+        let reused = |other: &str, settings: &[(&str, toml::Value)], now| {
+            // cached chapters are marked, so a reused one shows
+            if let Ok(cached) = fs::read_to_string(&cache) {
+                fs::write(&cache, cached.replace("<td>docs</td>", "<td>cached</td>")).unwrap();
+            }
+            let mut book = Book::new();
+            book.sections = vec![
+                chapter("Cached", "+++\nowner: docs\n+++\n", Some(1)),
+                chapter("Other", &format!("+++\nowner: {}\n+++\n", other), Some(2)),
+            ];
+            let book = FrontmatterPreprocessor
+                .run_at(&context(root.path(), settings), book, now)
+                .unwrap();
+            chapters(&book)[0].content.contains("<td>cached</td>")
+        };
+
+        assert!(!reused("ops", &settings, today));
+        assert!(reused("ops", &settings, today));
+        // another chapter's keys
+        assert!(!reused("sre", &settings, today));
+        assert!(reused("sre", &settings, today));
+        // book.toml
+        assert!(!reused("sre", &captioned, today));
+        assert!(reused("sre", &captioned, today));
+        // the day
+        assert!(!reused("sre", &captioned, tomorrow));
+        assert!(reused("sre", &captioned, tomorrow));
+    }
 }
@@ -0,0 +1,410 @@
+//! Turn handles, emails, etc. inside frontmatter values into anchor tags.
+use crate::config::{Config, EmailObfuscation, LinkifyRule};
+use mdbook::errors::Error;
+use regex::{Captures, Regex};
+
+/// Compiled linkification rules.
+///
+/// Built-in rules come first, followed by any rules from `book.toml`.
+pub(crate) struct Linkifier {
+    rules: Vec<CompiledRule>,
+    email_obfuscation: EmailObfuscation,
+}
+
+struct CompiledRule {
+    regex: Regex,
+    rule: LinkifyRule,
+}
+
+impl Linkifier {
+    /// Compile the built-in rules and the user-defined rules from config.
+    ///
+    /// Bare URLs are linked last so more specific rules win ties.
+    pub(crate) fn new(config: &Config) -> Result<Self, Error> {
+        let rules = builtin_rules(config)
+            .into_iter()
+            .chain(config.linkify.iter().cloned())
+            .chain(config.autolink_urls.then(|| url_rule(config)))
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern).map_err(|e| {
+                    Error::msg(format!("invalid linkify pattern {:?}: {}", rule.pattern, e))
+                })?;
+                Ok(CompiledRule { regex, rule })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            rules,
+            email_obfuscation: config.email_obfuscation,
+        })
+    }
+
+    /// Create anchor tags inside the value for `key`.
+    ///
+    /// The text is scanned once and the earliest match of any rule wins, so
+    /// rules never see anchors produced by other rules.
+    pub(crate) fn linkify(&self, key: &str, text: &str) -> String {
+        let rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|r| r.rule.applies_to(key))
+            .collect();
+        if rules.is_empty() {
+            return text.to_string();
+        }
+
+        let mut linked = String::with_capacity(text.len());
+        let mut pos = 0;
+        while pos < text.len() {
+            let next = rules
+                .iter()
+                .filter_map(|r| {
+                    r.regex
+                        .captures_at(text, pos)
+                        .filter(|caps| !caps[0].is_empty())
+                        .map(|caps| (r, caps))
+                })
+                .min_by_key(|(_, caps)| caps.get_match().start());
+
+            let Some((rule, caps)) = next else { break };
+            let whole = caps.get_match();
+            linked.push_str(&text[pos..whole.start()]);
+            linked.push_str(&rule.render(&caps, self.email_obfuscation));
+            pos = whole.end();
+        }
+        linked.push_str(&text[pos..]);
+        linked
+    }
+}
+
+impl CompiledRule {
+    /// Replace the match (or its `link` group) with an anchor.
+    fn render(&self, caps: &Captures, email_obfuscation: EmailObfuscation) -> String {
+        let whole = caps.get_match();
+        let span = caps.name("link").unwrap_or(whole);
+
+        let mut href = String::new();
+        caps.expand(&self.rule.replacement, &mut href);
+
+        let mut text = match &self.rule.text {
+            Some(template) => {
+                let mut text = String::new();
+                caps.expand(template, &mut text);
+                text
+            }
+            None => span.as_str().to_string(),
+        };
+
+        if href.starts_with("mailto:") {
+            match email_obfuscation {
+                EmailObfuscation::None => (),
+                EmailObfuscation::Entities => {
+                    href = encode_entities(&href);
+                    text = encode_entities(&text);
+                }
+                EmailObfuscation::At => {
+                    href = encode_entities(&href);
+                    text = text.replace('@', " [at] ");
+                }
+            }
+        }
+
+        let matched = whole.as_str();
+        format!(
+            "{}<a href=\"{}\">{}</a>{}",
+            &matched[..span.start() - whole.start()],
+            href,
+            text,
+            &matched[span.end() - whole.start()..]
+        )
+    }
+}
+
+/// GitHub/GitLab usernames and emails in parentheses, e.g. "Name (@user)",
+/// Fediverse handles, ORCID iDs, DOIs and issue references.
+fn builtin_rules(config: &Config) -> Vec<LinkifyRule> {
+    let mut rules = vec![];
+    if config.linkify_github {
+        let base_url = url_template(&config.github_base_url);
+        rules.push(LinkifyRule {
+            text: config.github_avatars.then(|| {
+                format!(
+                    "<img class=\"fm-avatar\" src=\"{}/${{user}}.png?size=40\" \
+                     alt=\"\" width=\"20\" height=\"20\" /> @$user",
+                    base_url
+                )
+            }),
+            ..LinkifyRule::new(
+                r"\((?P<link>@(?P<user>[a-zA-Z0-9_]+))\)",
+                &format!("{}/$user", base_url),
+            )
+        });
+    }
+    if config.linkify_gitlab {
+        rules.push(LinkifyRule::new(
+            r"\((?P<link>~(?P<user>[a-zA-Z0-9_.-]+))\)",
+            &format!("{}/$user", url_template(&config.gitlab_base_url)),
+        ));
+    }
+    if config.linkify_fediverse {
+        // `\B` keeps the handle from starting in the middle of a word
+        rules.push(LinkifyRule {
+            keys: vec!["author".to_string(), "contact".to_string()],
+            ..LinkifyRule::new(
+                r"\B@(?P<user>[a-zA-Z0-9_]+)@(?P<host>[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)+)",
+                "https://$host/@$user",
+            )
+        });
+    }
+    if config.linkify_orcid {
+        rules.push(LinkifyRule {
+            text: Some(format!("{ORCID_ICON} $id")),
+            ..LinkifyRule::new(
+                r"\b(?:https?://orcid\.org/)?(?P<id>\d{4}-\d{4}-\d{4}-\d{3}[\dX])\b",
+                "https://orcid.org/$id",
+            )
+        });
+    }
+    if !config.doi_keys.is_empty() {
+        // accept bare, `doi:` prefixed and resolver URL forms, and don't
+        // swallow trailing punctuation
+        rules.push(LinkifyRule {
+            text: Some("$doi".to_string()),
+            keys: config.doi_keys.clone(),
+            ..LinkifyRule::new(
+                r#"\b(?:doi:\s*|https?://(?:dx\.)?doi\.org/)?(?P<doi>10\.\d{4,9}/[^\s"<>]*[^\s"<>.,;:)])"#,
+                "https://doi.org/$doi",
+            )
+        });
+    }
+    if let Some(repository_url) = &config.repository_url {
+        // GitHub and GitLab both redirect `/issues/N` to the PR/MR if needed
+        rules.push(LinkifyRule {
+            keys: config.issue_keys.clone(),
+            ..LinkifyRule::new(
+                r"\B#(?P<number>\d+)\b",
+                &format!("{}/issues/$number", url_template(repository_url)),
+            )
+        });
+    }
+    rules.push(LinkifyRule::new(
+        r"\((?P<link>[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)\)",
+        "mailto:$link",
+    ));
+    rules
+}
+
+/// Bare URLs in any value.
+///
+/// The URL must start the value or follow whitespace or an opening bracket, so
+/// URLs inside attributes of hand-written HTML are left alone.
+fn url_rule(config: &Config) -> LinkifyRule {
+    LinkifyRule {
+        keys: vec!["*".to_string()],
+        skip_keys: config.autolink_skip_keys.clone(),
+        ..LinkifyRule::new(
+            r#"(?:^|[\s(\[])(?P<link>https?://[^\s<>"]*[^\s<>".,;:!?)\]'])"#,
+            "$link",
+        )
+    }
+}
+
+/// Write every character as a decimal HTML character reference.
+fn encode_entities(text: &str) -> String {
+    text.chars().map(|c| format!("&#{};", c as u32)).collect()
+}
+
+/// The iD icon from ORCID's display guidelines.
+const ORCID_ICON: &str = "<img alt=\"ORCID logo\" \
+    src=\"https://info.orcid.org/wp-content/uploads/2019/11/orcid_16x16.png\" \
+    width=\"16\" height=\"16\" />";
+
+/// Use a configured base URL literally inside a replacement template.
+fn url_template(base_url: &str) -> String {
+    base_url.trim_end_matches('/').replace('$', "$$")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every built-in rule on, and bare URLs linked.
+    fn all_rules() -> Config {
+        Config {
+            linkify_gitlab: true,
+            linkify_fediverse: true,
+            linkify_orcid: true,
+            autolink_urls: true,
+            repository_url: Some("https://github.com/org/repo".to_string()),
+            ..Config::default()
+        }
+    }
+
+    fn linkify(key: &str, text: &str) -> String {
+        Linkifier::new(&all_rules()).unwrap().linkify(key, text)
+    }
+
+    #[test]
+    fn github_handles() {
+        assert_eq!(
+            linkify("author", "Ann (@ann)"),
+            "Ann (<a href=\"https://github.com/ann\">@ann</a>)"
+        );
+        let config = Config {
+            github_base_url: "https://git.corp/".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(
+            Linkifier::new(&config).unwrap().linkify("author", "(@ann)"),
+            "(<a href=\"https://git.corp/ann\">@ann</a>)"
+        );
+        // only in parentheses, and only in author values
+        assert_eq!(linkify("author", "@ann"), "@ann");
+        assert_eq!(linkify("owner", "(@ann)"), "(@ann)");
+    }
+
+    #[test]
+    fn gitlab_handles() {
+        assert_eq!(
+            linkify("author", "Bo (~bo.b)"),
+            "Bo (<a href=\"https://gitlab.com/bo.b\">~bo.b</a>)"
+        );
+    }
+
+    #[test]
+    fn fediverse_handles() {
+        assert_eq!(
+            linkify("contact", "@ann@hachyderm.io"),
+            "<a href=\"https://hachyderm.io/@ann\">@ann@hachyderm.io</a>"
+        );
+        assert_eq!(linkify("tags", "@ann@hachyderm.io"), "@ann@hachyderm.io");
+    }
+
+    #[test]
+    fn orcid_ids() {
+        let linked = linkify("author", "Eve 0000-0002-1825-0097");
+        assert!(
+            linked.starts_with("Eve <a href=\"https://orcid.org/0000-0002-1825-0097\"><img"),
+            "{}",
+            linked
+        );
+        assert!(linked.ends_with(" 0000-0002-1825-0097</a>"), "{}", linked);
+    }
+
+    #[test]
+    fn dois() {
+        assert_eq!(
+            linkify("doi", "doi:10.1000/xyz.12."),
+            "<a href=\"https://doi.org/10.1000/xyz.12\">10.1000/xyz.12</a>."
+        );
+    }
+
+    #[test]
+    fn issue_references() {
+        assert_eq!(
+            linkify("tracking-issue", "#482"),
+            "<a href=\"https://github.com/org/repo/issues/482\">#482</a>"
+        );
+        assert_eq!(linkify("author", "#482"), "#482");
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(
+            linkify("author", "Bob (bob@x.com)"),
+            "Bob (<a href=\"mailto:bob@x.com\">bob@x.com</a>)"
+        );
+        let config = Config {
+            email_obfuscation: EmailObfuscation::At,
+            ..Config::default()
+        };
+        let linked = Linkifier::new(&config)
+            .unwrap()
+            .linkify("author", "(a@b.io)");
+        assert!(linked.ends_with(">a [at] b.io</a>)"), "{}", linked);
+        assert!(!linked.contains("mailto"), "{}", linked);
+    }
+
+    #[test]
+    fn bare_urls() {
+        assert_eq!(
+            linkify("spec", "See https://example.com/a."),
+            "See <a href=\"https://example.com/a\">https://example.com/a</a>."
+        );
+        // not inside hand-written attributes
+        assert_eq!(
+            linkify("spec", "<a href=\"https://example.com\">x</a>"),
+            "<a href=\"https://example.com\">x</a>"
+        );
+        let config = Config {
+            autolink_skip_keys: vec!["spec".to_string()],
+            ..all_rules()
+        };
+        assert_eq!(
+            Linkifier::new(&config)
+                .unwrap()
+                .linkify("spec", "https://example.com"),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn custom_rules_after_builtins() {
+        let config = Config {
+            linkify: vec![LinkifyRule {
+                text: Some("JIRA-$id".to_string()),
+                ..LinkifyRule::new(r"JIRA-(?P<id>\d+)", "https://jira/$id")
+            }],
+            ..Config::default()
+        };
+        assert_eq!(
+            Linkifier::new(&config)
+                .unwrap()
+                .linkify("author", "JIRA-7 (@ann)"),
+            "<a href=\"https://jira/7\">JIRA-7</a> (<a href=\"https://github.com/ann\">@ann</a>)"
+        );
+    }
+
+    #[test]
+    fn email_inside_a_url_stays_part_of_it() {
+        assert_eq!(
+            linkify("author", "(https://lists.x.com/u/bob@x.com)"),
+            "(<a href=\"https://lists.x.com/u/bob@x.com\">https://lists.x.com/u/bob@x.com</a>)"
+        );
+    }
+
+    #[test]
+    fn handle_inside_an_email_isnt_linked() {
+        assert_eq!(
+            linkify("author", "(ann@hachyderm.io)"),
+            "(<a href=\"mailto:ann@hachyderm.io\">ann@hachyderm.io</a>)"
+        );
+    }
+
+    #[test]
+    fn fediverse_handle_in_parentheses_isnt_a_github_handle() {
+        assert_eq!(
+            linkify("author", "Ann (@ann@hachyderm.io)"),
+            "Ann (<a href=\"https://hachyderm.io/@ann\">@ann@hachyderm.io</a>)"
+        );
+    }
+
+    #[test]
+    fn specific_rules_win_over_bare_urls() {
+        assert_eq!(
+            linkify("doi", "https://doi.org/10.1000/xyz"),
+            "<a href=\"https://doi.org/10.1000/xyz\">10.1000/xyz</a>"
+        );
+        let orcid = linkify("author", "https://orcid.org/0000-0002-1825-0097");
+        assert!(
+            orcid.starts_with("<a href=\"https://orcid.org/0000-0002-1825-0097\"><img"),
+            "{}",
+            orcid
+        );
+        // a fragment isn't an issue reference
+        assert_eq!(
+            linkify("issue", "https://x.com/page#12"),
+            "<a href=\"https://x.com/page#12\">https://x.com/page#12</a>"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Generated chapters summarizing the frontmatter of every chapter.
+use crate::catalog::{Catalog, Entry};
+use crate::config::{Config, DateDisplay};
+use crate::date;
+use crate::head::escape;
+use crate::output;
+use crate::table;
+use crate::taxonomy::{generated_chapter, markdown_text, relative_link};
+use mdbook::book::Chapter;
+use std::path::{Path, PathBuf};
+
+/// Sorts a table by the column whose header is clicked.
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll("table.fm-index").forEach(function (table) {
+    table.querySelectorAll("th").forEach(function (th, column) {
+        th.style.cursor = "pointer";
+        th.addEventListener("click", function () {
+            var body = table.tBodies[0];
+            var ascending = th.dataset.order !== "asc";
+            th.dataset.order = ascending ? "asc" : "desc";
+            Array.from(body.rows)
+                .sort(function (a, b) {
+                    var x = a.cells[column].textContent, y = b.cells[column].textContent;
+                    return (ascending ? 1 : -1) * x.localeCompare(y, undefined, { numeric: true });
+                })
+                .forEach(function (row) { body.appendChild(row); });
+        });
+    });
+});
+</script>"#;
+
+/// A chapter with a table of every chapter's `document_index_columns`,
+/// optionally one table per part.
+pub(crate) fn document_index(catalog: &Catalog, config: &Config) -> Chapter {
+    let path = PathBuf::from(&config.document_index_path);
+    let mut content = format!("# {}\n\n", config.document_index_title);
+
+    if config.document_index_by_part {
+        let mut parts: Vec<(Option<&str>, Vec<&Entry>)> = vec![];
+        for entry in &catalog.entries {
+            match parts.last_mut() {
+                Some((part, entries)) if *part == entry.part.as_deref() => entries.push(entry),
+                _ => parts.push((entry.part.as_deref(), vec![entry])),
+            }
+        }
+        for (part, entries) in parts {
+            if let Some(part) = part {
+                content.push_str(&format!("## {}\n\n", part));
+            }
+            content.push_str(&index_table(&entries, &path, config));
+        }
+    } else {
+        let entries: Vec<_> = catalog.entries.iter().collect();
+        content.push_str(&index_table(&entries, &path, config));
+    }
+    content.push_str(SORT_SCRIPT);
+    content.push('\n');
+
+    generated_chapter(&config.document_index_title, content, path, vec![])
+}
+
+fn index_table(entries: &[&Entry], path: &Path, config: &Config) -> String {
+    let mut html = String::from("<table class=\"fm-index\">\n<thead><tr>");
+    for column in &config.document_index_columns {
+        html.push_str(&format!("<th>{}</th>", table::label(column, config)));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for entry in entries {
+        html.push_str("<tr>");
+        for column in &config.document_index_columns {
+            let cell = if column == "title" {
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    output::relative_url(path, &entry.path),
+                    escape(&entry.title)
+                )
+            } else {
+                entry.value(column).unwrap_or_default().to_string()
+            };
+            html.push_str(&format!("<td>{}</td>", cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n\n");
+    html
+}
+
+/// A "What's new" chapter listing every chapter with a `date` and a
+/// `changelog` (or `summary`) entry, newest first.
+pub(crate) fn changelog(catalog: &Catalog, config: &Config) -> Chapter {
+    let path = PathBuf::from(&config.changelog_path);
+    let mut changes: Vec<_> = catalog
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let value = entry.value("date")?;
+            let date = date::parse_date(value)?;
+            let summary = entry
+                .value("changelog")
+                .or_else(|| entry.value("summary"))?;
+            Some((date, value, entry, summary))
+        })
+        .collect();
+    // newest first; the sort is stable so same-day changes keep book order
+    changes.sort_by_key(|(date, ..)| std::cmp::Reverse(*date));
+
+    let format = config
+        .date_format
+        .as_deref()
+        .unwrap_or(date::DEFAULT_DATE_FORMAT);
+    let mut content = format!("# {}\n\n", config.changelog_title);
+    let limit = config.changelog_limit.unwrap_or(usize::MAX);
+    for (_, value, entry, summary) in changes.into_iter().take(limit) {
+        content.push_str(&format!(
+            "- **{}** [{}]({}): {}\n",
+            date::render_date(
+                value,
+                Some(format),
+                DateDisplay::Absolute,
+                config.language.as_deref()
+            ),
+            markdown_text(&entry.title),
+            relative_link(&path, &entry.path),
+            summary
+        ));
+    }
+
+    generated_chapter(&config.changelog_title, content, path, vec![])
+}
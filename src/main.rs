@@ -1,7 +1,15 @@
-use process_frontmatter::FrontmatterPreprocessor;
+use log::LevelFilter;
+use mdbook::errors::Error;
+use mdbook::preprocess::Preprocessor;
+use process_frontmatter::{ExportFormat, FrontmatterPreprocessor, FrontmatterStyle};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
 
 /// Main function for preprocessing data in frontmatter
 fn main() {
+    init_logger();
+
     // capture args from env
     let args: Vec<String> = std::env::args().collect();
 
@@ -12,18 +20,181 @@ fn main() {
     // 2) expects json from stdin
     if args.len() > 2 && args[1] == "supports" {
         // Check if the preprocessor supports the specified renderer
-        match args[2].as_str() {
-            // supports HTML renderer
-            "html" => std::process::exit(0),
+        if FrontmatterPreprocessor.supports_renderer(&args[2]) {
+            std::process::exit(0)
+        } else {
             // untested
-            _ => std::process::exit(1),
+            std::process::exit(1)
+        }
+    } else if args.len() > 1 && args[1] == "export" {
+        // process-frontmatter export [--tsv] [book dir]
+        let format = if args.iter().any(|arg| arg == "--tsv") {
+            ExportFormat::Tsv
+        } else {
+            ExportFormat::Csv
+        };
+        export(&args[2..], format);
+    } else if args.len() > 1 && args[1] == "extract" {
+        // process-frontmatter extract [--format json|toml|csv|tsv|paths] [book dir]
+        let format = match option(&args[2..], "--format").map(str::parse) {
+            Some(Ok(format)) => format,
+            Some(Err(e)) => {
+                eprintln!("Error extracting frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
+            None => ExportFormat::Json,
+        };
+        export(&args[2..], format);
+    } else if args.len() > 2 && args[1] == "query" {
+        // process-frontmatter query <query> [--format paths|json|..] [book dir]
+        let format = match option(&args[3..], "--format").map(str::parse) {
+            Some(Ok(format)) => format,
+            Some(Err(e)) => {
+                eprintln!("Error querying frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
+            None => ExportFormat::Paths,
+        };
+        let root = book_dir(&args[3..], &["--format"]);
+        match FrontmatterPreprocessor.query(Path::new(root), &args[2], format) {
+            Ok(chapters) => print!("{}", chapters),
+            Err(e) => {
+                eprintln!("Error querying frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    } else if args.len() > 1 && args[1] == "migrate" {
+        // process-frontmatter migrate [--from yaml|toml|jekyll|frontmatter]
+        //   [--to frontmatter|yaml|toml] [--dry-run] [book dir]
+        let style = |name, default| match option(&args[2..], name).map(str::parse) {
+            Some(Ok(style)) => style,
+            Some(Err(e)) => {
+                eprintln!("Error migrating frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
+            None => default,
+        };
+        let from = style("--from", FrontmatterStyle::Yaml);
+        let to = style("--to", FrontmatterStyle::Frontmatter);
+        let dry_run = args.iter().any(|arg| arg == "--dry-run");
+        let root = book_dir(&args[2..], &["--from", "--to"]);
+        match FrontmatterPreprocessor.migrate(Path::new(root), from, to, dry_run) {
+            Ok(report) => print!("{}", report),
+            Err(e) => {
+                eprintln!("Error migrating frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    } else if args.len() > 1 && args[1] == "install" {
+        // process-frontmatter install [book dir]
+        let root = book_dir(&args[2..], &[]);
+        if let Err(e) = FrontmatterPreprocessor.install(Path::new(root)) {
+            eprintln!("Error installing frontmatter: {:?}", e);
+            std::process::exit(1);
+        }
+    } else if args.len() > 1 && args[1] == "diff" {
+        // process-frontmatter diff [book dir]
+        let root = book_dir(&args[2..], &[]);
+        match FrontmatterPreprocessor.diff(Path::new(root)) {
+            Ok(diff) => print!("{}", diff),
+            Err(e) => {
+                eprintln!("Error diffing frontmatter: {:?}", e);
+                std::process::exit(1);
+            }
         }
     } else {
         // Normal operation, not checking for renderer support
-        let backend = FrontmatterPreprocessor::default();
-        if let Err(e) = backend.handle_preprocessing() {
+        //
+        // --input/--output replay a saved payload instead of stdin/stdout
+        let backend = FrontmatterPreprocessor;
+        let input = option(&args[1..], "--input");
+        let output = option(&args[1..], "--output");
+        let result = match (input, output) {
+            (None, None) => backend.handle_preprocessing(),
+            // the output is only written once the book was processed, so a
+            // failed replay leaves an existing file alone
+            _ => reader(input).and_then(|input| {
+                let mut processed = vec![];
+                backend.process(input, &mut processed)?;
+                write_output(output, &processed)
+            }),
+        };
+        if let Err(e) = result {
             eprintln!("Error processing frontmatter: {:?}", e);
             std::process::exit(1);
         }
     }
 }
+
+/// Print the frontmatter of the book named in `args` in `format`.
+fn export(args: &[String], format: ExportFormat) {
+    let root = book_dir(args, &["--format"]);
+    match FrontmatterPreprocessor.export(Path::new(root), format) {
+        Ok(export) => print!("{}", export),
+        Err(e) => {
+            eprintln!("Error exporting frontmatter: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The file named by `--input`, or stdin.
+fn reader(path: Option<&str>) -> Result<Box<dyn Read>, Error> {
+    match path {
+        Some(path) => File::open(path)
+            .map(|file| Box::new(file) as Box<dyn Read>)
+            .map_err(|e| Error::msg(format!("unable to read {}: {}", path, e))),
+        None => Ok(Box::new(std::io::stdin())),
+    }
+}
+
+/// Write `processed` to the file named by `--output`, or stdout.
+fn write_output(path: Option<&str>, processed: &[u8]) -> Result<(), Error> {
+    match path {
+        Some(path) => std::fs::write(path, processed)
+            .map_err(|e| Error::msg(format!("unable to write {}: {}", path, e))),
+        None => Ok(std::io::stdout().write_all(processed)?),
+    }
+}
+
+/// The value of `--name value` or `--name=value` in `args`.
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).map(String::as_str)
+        } else {
+            arg.strip_prefix(name)?.strip_prefix('=')
+        }
+    })
+}
+
+/// The first argument that's neither a flag nor the value of one of
+/// `options`, or the current directory.
+fn book_dir<'a>(args: &'a [String], options: &[&str]) -> &'a str {
+    args.iter()
+        .enumerate()
+        .find(|(i, arg)| {
+            !arg.starts_with("--") && (*i == 0 || !options.contains(&args[i - 1].as_str()))
+        })
+        .map_or(".", |(_, arg)| arg.as_str())
+}
+
+/// Log to stderr in mdbook's format, honoring `RUST_LOG`.
+fn init_logger() {
+    let mut builder = env_logger::Builder::new();
+    builder.format(|formatter, record| {
+        writeln!(
+            formatter,
+            "{} [{}] ({}): {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            record.level(),
+            record.target(),
+            record.args()
+        )
+    });
+    match std::env::var("RUST_LOG") {
+        Ok(filters) => builder.parse_filters(&filters),
+        Err(_) => builder.filter_level(LevelFilter::Info),
+    };
+    builder.init();
+}
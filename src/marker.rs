@@ -0,0 +1,21 @@
+//! A chapter's keys as an invisible html comment at its top, so preprocessors
+//! running after this one can read them without parsing anything else:
+//!
+//! `<!-- frontmatter: {"status":"Draft","title":"Intro"} -->`
+use serde_json::{json, Map, Value};
+
+/// Opens the comment, right before the JSON object.
+pub(crate) const PREFIX: &str = "<!-- frontmatter: ";
+
+/// The comment for `frontmatter`, followed by a blank line. A key written
+/// more than once keeps its first value, as in the JSON export.
+pub(crate) fn comment(frontmatter: &[(String, String)]) -> String {
+    let mut keys = Map::new();
+    for (key, value) in frontmatter {
+        keys.entry(key.clone()).or_insert_with(|| json!(value));
+    }
+    // "--" only occurs in strings, where "--" means the same and can't
+    // end the comment early
+    let json = Value::Object(keys).to_string().replace("--", "-\\u002d");
+    format!("{}{} -->\n\n", PREFIX, json)
+}
@@ -0,0 +1,478 @@
+//! Rewrite the frontmatter at the top of chapter files between the styles of
+//! other tools and this one.
+use crate::diff;
+use crate::output;
+use crate::parse::{BOM, DELIMITER};
+use log::warn;
+use mdbook::errors::Error;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// The date a Jekyll post's file name starts with.
+static DATED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4}-\d{2}-\d{2})-").expect("valid regex"));
+
+/// How a chapter's frontmatter is written, for
+/// [FrontmatterPreprocessor::migrate](crate::FrontmatterPreprocessor::migrate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterStyle {
+    /// `key: value` lines between `+++`, as this preprocessor reads them.
+    Frontmatter,
+    /// YAML between `---`, as Jekyll, Hugo and most static site generators
+    /// write it.
+    Yaml,
+    /// TOML between `+++`, as Hugo writes it.
+    Toml,
+    /// YAML from Jekyll posts, which also takes the `date` from a
+    /// `YYYY-MM-DD-title.md` file name and splits space separated
+    /// `categories` and `tags`. Written out as YAML.
+    Jekyll,
+}
+
+impl FromStr for FrontmatterStyle {
+    type Err = Error;
+
+    fn from_str(style: &str) -> Result<Self, Error> {
+        match style {
+            "frontmatter" => Ok(Self::Frontmatter),
+            "yaml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            "jekyll" => Ok(Self::Jekyll),
+            _ => Err(Error::msg(format!(
+                "style must be \"frontmatter\", \"yaml\", \"toml\" or \"jekyll\", not {:?}",
+                style
+            ))),
+        }
+    }
+}
+
+impl FrontmatterStyle {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Frontmatter => "frontmatter",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Jekyll => "jekyll",
+        }
+    }
+
+    fn delimiter(self) -> &'static str {
+        match self {
+            Self::Frontmatter | Self::Toml => DELIMITER,
+            Self::Yaml | Self::Jekyll => "---",
+        }
+    }
+}
+
+/// Convert every markdown file under `src_dir` starting with a `from` block,
+/// returning a diff of the changes when `dry_run` is set and the rewritten
+/// files otherwise.
+pub(crate) fn run(
+    src_dir: &Path,
+    from: FrontmatterStyle,
+    to: FrontmatterStyle,
+    dry_run: bool,
+) -> Result<String, Error> {
+    let mut files = vec![];
+    markdown_files(src_dir, &mut files)?;
+    files.sort();
+
+    let mut report = String::new();
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .map_err(|e| Error::msg(format!("unable to read {}: {}", file.display(), e)))?;
+        let name = file
+            .strip_prefix(src_dir)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        let Some(converted) = convert(&content, from, to, &name) else {
+            continue;
+        };
+        if converted == content {
+            continue;
+        }
+        if dry_run {
+            report.push_str(&diff::unified(
+                &content,
+                &converted,
+                &format!("a/{}", name),
+                &format!("b/{}", name),
+            ));
+        } else {
+            output::write_if_changed(&file, &converted)?;
+            report.push_str(&name);
+            report.push('\n');
+        }
+    }
+    Ok(report)
+}
+
+/// Every `.md` file under `dir` but `SUMMARY.md`.
+fn markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| Error::msg(format!("unable to read {}: {}", dir.display(), e)))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") && !path.ends_with("SUMMARY.md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `content` with its leading `from` block rewritten in the `to` style, or
+/// `None` if it doesn't start with one.
+pub(crate) fn convert(
+    content: &str,
+    from: FrontmatterStyle,
+    to: FrontmatterStyle,
+    name: &str,
+) -> Option<String> {
+    let (start, lines, end) = leading_block(content, from.delimiter())?;
+    let mut frontmatter = match from {
+        FrontmatterStyle::Frontmatter => read_frontmatter(&lines),
+        FrontmatterStyle::Yaml | FrontmatterStyle::Jekyll => read_yaml(&lines, name),
+        FrontmatterStyle::Toml => read_toml(&lines, name),
+    };
+    if frontmatter.is_empty() && lines.iter().any(|line| !line.trim().is_empty()) {
+        warn!("{}: no {} keys found, leaving it as is", name, from.name());
+        return None;
+    }
+    if from == FrontmatterStyle::Jekyll {
+        jekyll(&mut frontmatter, name);
+    }
+
+    let block = match to {
+        FrontmatterStyle::Frontmatter => write_frontmatter(&frontmatter),
+        FrontmatterStyle::Yaml | FrontmatterStyle::Jekyll => write_yaml(&frontmatter),
+        FrontmatterStyle::Toml => write_toml(&frontmatter),
+    };
+    Some(format!("{}{}{}", &content[..start], block, &content[end..]))
+}
+
+/// Byte range of a block opened by `delimiter` on the first non-blank line,
+/// and the lines inside it.
+fn leading_block<'a>(content: &'a str, delimiter: &str) -> Option<(usize, Vec<&'a str>, usize)> {
+    // a byte order mark stays where it is, before the block
+    let mut offset = content.len() - content.trim_start_matches(BOM).len();
+    let mut start = None;
+    let mut lines = vec![];
+    for line in content[offset..].split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        offset += line.len();
+        match start {
+            None if text.trim().is_empty() => (),
+            None if text.trim_end() == delimiter => start = Some(offset - line.len()),
+            None => return None,
+            Some(start) if text.trim_end() == delimiter => {
+                // keep the line break after the closing delimiter
+                let end = offset - (line.len() - text.len());
+                return Some((start, lines, end));
+            }
+            Some(_) => lines.push(text),
+        }
+    }
+    None
+}
+
+fn read_frontmatter(lines: &[&str]) -> Vec<(String, String)> {
+    lines
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The flat subset of YAML frontmatter uses: scalars, `- item` and `[..]`
+/// lists, and `>`/`|` text blocks (joined into one line). Nested mappings
+/// are skipped with a warning.
+fn read_yaml(lines: &[&str], name: &str) -> Vec<(String, String)> {
+    let mut frontmatter: Vec<(String, String)> = vec![];
+    let mut items: Option<Vec<String>> = None;
+    let mut text: Option<Vec<String>> = None;
+    let mut nested = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indented = line.starts_with([' ', '\t']);
+        if indented || trimmed.starts_with("- ") || trimmed == "-" {
+            if let Some(text) = text.as_mut() {
+                text.push(trimmed.to_string());
+            } else if let Some(item) = trimmed.strip_prefix('-') {
+                items.get_or_insert_with(Vec::new).push(scalar(item.trim()));
+            } else if !nested {
+                // a key holding a mapping has no value of its own
+                if frontmatter
+                    .last()
+                    .is_some_and(|(_, value)| value.is_empty())
+                {
+                    if let Some((key, _)) = frontmatter.pop() {
+                        warn!("{}: skipping nested YAML under {}", name, key);
+                    }
+                }
+                nested = true;
+            }
+            continue;
+        }
+
+        finish_yaml(&mut frontmatter, &mut items, &mut text);
+        nested = false;
+        let Some((key, value)) = trimmed.split_once(':') else {
+            warn!("{}: skipping YAML line {:?}", name, trimmed);
+            continue;
+        };
+        let value = value.trim();
+        if matches!(value.chars().next(), Some('>' | '|')) {
+            text = Some(vec![]);
+        }
+        frontmatter.push((scalar(key.trim()), scalar(value)));
+    }
+    finish_yaml(&mut frontmatter, &mut items, &mut text);
+    frontmatter
+}
+
+/// Put a finished `- item` list or text block into the last key's value.
+fn finish_yaml(
+    frontmatter: &mut [(String, String)],
+    items: &mut Option<Vec<String>>,
+    text: &mut Option<Vec<String>>,
+) {
+    if let (Some((_, value)), Some(items)) = (frontmatter.last_mut(), items.take()) {
+        *value = format!("[{}]", items.join(", "));
+    }
+    if let (Some((_, value)), Some(text)) = (frontmatter.last_mut(), text.take()) {
+        *value = text.join(" ");
+    }
+}
+
+/// A YAML scalar without its quotes or trailing comment.
+fn scalar(value: &str) -> String {
+    if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.rsplit_once('"')) {
+        return quoted.0.replace("\\\"", "\"").replace("\\\\", "\\");
+    }
+    if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.rsplit_once('\'')) {
+        return quoted.0.replace("''", "'");
+    }
+    match value.find(" #") {
+        Some(comment) => value[..comment].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Top-level TOML keys, in the order written. Keys under a `[table]` are
+/// skipped with a warning.
+fn read_toml(lines: &[&str], name: &str) -> Vec<(String, String)> {
+    let mut frontmatter = vec![];
+    let mut entry = String::new();
+    for line in lines {
+        if entry.is_empty() && line.trim_start().starts_with('[') {
+            warn!("{}: skipping TOML from {} on", name, line.trim());
+            break;
+        }
+        entry.push_str(line);
+        entry.push('\n');
+        // a value may span lines, like an array
+        let Ok(table) = toml::from_str::<toml::value::Table>(&entry) else {
+            continue;
+        };
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::Array(items) => format!(
+                    "[{}]",
+                    items
+                        .into_iter()
+                        .map(crate::value::from_toml)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                toml::Value::Table(_) => {
+                    warn!("{}: skipping TOML table {}", name, key);
+                    continue;
+                }
+                value => crate::value::from_toml(value),
+            };
+            frontmatter.push((key, value));
+        }
+        entry.clear();
+    }
+    frontmatter
+}
+
+/// Jekyll's conventions: the post date in the file name, and space separated
+/// `categories` and `tags`.
+fn jekyll(frontmatter: &mut Vec<(String, String)>, name: &str) {
+    for (key, value) in frontmatter.iter_mut() {
+        if (key == "categories" || key == "tags") && !value.starts_with('[') && !value.contains(',')
+        {
+            *value = format!(
+                "[{}]",
+                value.split_whitespace().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+
+    let file_name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    if let Some(date) = DATED.captures(&file_name) {
+        if !frontmatter.iter().any(|(key, _)| key == "date") {
+            frontmatter.push(("date".to_string(), date[1].to_string()));
+        }
+    }
+}
+
+fn write_frontmatter(frontmatter: &[(String, String)]) -> String {
+    let mut block = format!("{}\n", DELIMITER);
+    for (key, value) in frontmatter {
+        block.push_str(&format!("{}: {}\n", key, value));
+    }
+    block + DELIMITER
+}
+
+fn write_yaml(frontmatter: &[(String, String)]) -> String {
+    let mut block = "---\n".to_string();
+    for (key, value) in frontmatter {
+        block.push_str(&format!("{}: {}\n", yaml_scalar(key), yaml_scalar(value)));
+    }
+    block + "---"
+}
+
+/// A value as YAML, quoted unless it reads back the same bare. `[..]` lists
+/// are kept as flow sequences.
+fn yaml_scalar(value: &str) -> String {
+    let list = value.starts_with('[') && value.ends_with(']');
+    let special = value.is_empty()
+        || value.starts_with([
+            '{', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`', '#', '-', '?', ':', ',', ']',
+        ])
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.trim() != value;
+    if list || !(special || value.starts_with('[')) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn write_toml(frontmatter: &[(String, String)]) -> String {
+    let bare = |key: &str| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    let string = |value: &str| toml::Value::String(value.to_string()).to_string();
+
+    let mut block = format!("{}\n", DELIMITER);
+    for (key, value) in frontmatter {
+        let key = if bare(key) { key.clone() } else { string(key) };
+        let value = if value.starts_with('[') && value.ends_with(']') {
+            let items: Vec<_> = crate::value::split_list(value)
+                .iter()
+                .map(|item| string(item))
+                .collect();
+            format!("[{}]", items.join(", "))
+        } else {
+            string(value)
+        };
+        block.push_str(&format!("{} = {}\n", key, value));
+    }
+    block + DELIMITER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use FrontmatterStyle::*;
+
+    #[test]
+    fn yaml_becomes_frontmatter() {
+        let yaml = "---\ntitle: Setup\nowner: docs\ntags: [rust, mdbook]\n---\n\n# Setup\n";
+        assert_eq!(
+            convert(yaml, Yaml, Frontmatter, "setup.md").unwrap(),
+            "+++\ntitle: Setup\nowner: docs\ntags: [rust, mdbook]\n+++\n\n# Setup\n"
+        );
+    }
+
+    #[test]
+    fn jekyll_post_with_lists_and_quotes() {
+        let post = "---\n\
+            layout: post\n\
+            title: \"Hello: \\\"world\\\"\"\n\
+            summary: 'It''s here'\n\
+            tags: rust async\n\
+            authors:\n\
+            \x20 - Ann\n\
+            \x20 - \"Bob\"\n\
+            ---\n\
+            Text\n";
+        assert_eq!(
+            convert(post, Jekyll, Frontmatter, "_posts/2024-03-01-hello.md").unwrap(),
+            "+++\n\
+             layout: post\n\
+             title: Hello: \"world\"\n\
+             summary: It's here\n\
+             tags: [rust, async]\n\
+             authors: [Ann, Bob]\n\
+             date: 2024-03-01\n\
+             +++\n\
+             Text\n"
+        );
+    }
+
+    #[test]
+    fn colons_and_hashes_survive_a_round_trip() {
+        let chapter = "+++\n\
+            url: https://example.com/page#part\n\
+            ratio: 1:2 # not a comment\n\
+            note: #hashtag\n\
+            +++\n";
+        for style in [Yaml, Toml] {
+            let converted = convert(chapter, Frontmatter, style, "notes.md").unwrap();
+            assert_eq!(
+                convert(&converted, style, Frontmatter, "notes.md").unwrap(),
+                chapter,
+                "{}",
+                converted
+            );
+        }
+    }
+
+    #[test]
+    fn dry_run_leaves_files_alone() {
+        let src = tempfile::tempdir().unwrap();
+        let path = src.path().join("intro.md");
+        let yaml = "---\nowner: docs\n---\nIntro\n";
+        fs::write(&path, yaml).unwrap();
+
+        let diff = run(src.path(), Yaml, Frontmatter, true).unwrap();
+        assert!(diff.contains("\n----\n++++\n owner: docs\n"), "{}", diff);
+        assert_eq!(fs::read_to_string(&path).unwrap(), yaml);
+
+        assert_eq!(
+            run(src.path(), Yaml, Frontmatter, false).unwrap(),
+            "intro.md\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "+++\nowner: docs\n+++\nIntro\n"
+        );
+    }
+
+    #[test]
+    fn files_without_a_block_are_skipped() {
+        assert_eq!(convert("# Title\n---\n", Yaml, Frontmatter, "a.md"), None);
+    }
+}
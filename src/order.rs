@@ -0,0 +1,290 @@
+//! Reorder and renumber chapters from their frontmatter.
+use crate::config::ChapterOrder;
+use crate::date;
+use crate::value;
+use mdbook::book::{Chapter, SectionNumber};
+use mdbook::BookItem;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Sort the chapters of every part listed in `sort_parts`.
+///
+/// `value` looks up a key for a chapter. Chapters without the key go last,
+/// in their SUMMARY.md order. Returns whether anything was sorted.
+pub(crate) fn sort_parts(
+    sections: &mut [BookItem],
+    sort_parts: &HashMap<String, ChapterOrder>,
+    value: impl Fn(&Chapter, &str) -> Option<String>,
+) -> bool {
+    let mut sorted = false;
+    for (title, range) in part_ranges(sections) {
+        if let Some(order) = title.and_then(|title| sort_parts.get(&title)) {
+            sort_chapters(&mut sections[range], *order, &value);
+            sorted = true;
+        }
+    }
+    sorted
+}
+
+/// Sort the chapters of every part, and the sub-chapters of every chapter, by
+/// `weight`. Returns whether any chapter has a weight.
+pub(crate) fn sort_by_weight(
+    sections: &mut [BookItem],
+    value: impl Fn(&Chapter, &str) -> Option<String>,
+) -> bool {
+    let weighted = sections
+        .iter()
+        .flat_map(|item| chapter(item).into_iter().chain(descendants(item)))
+        .any(|c| weight(c, &value).is_some());
+    if weighted {
+        for (_, range) in part_ranges(sections) {
+            sort_tree(&mut sections[range], &value);
+        }
+    }
+    weighted
+}
+
+fn sort_tree(items: &mut [BookItem], value: &impl Fn(&Chapter, &str) -> Option<String>) {
+    sort_chapters(items, ChapterOrder::Weight, value);
+    for item in items {
+        if let BookItem::Chapter(chapter) = item {
+            sort_tree(&mut chapter.sub_items, value);
+        }
+    }
+}
+
+/// The title (if any) and item range of every part, the chapters before the
+/// first part title included.
+fn part_ranges(sections: &[BookItem]) -> Vec<(Option<String>, Range<usize>)> {
+    let mut ranges = vec![];
+    let mut title = None;
+    let mut start = 0;
+    for (i, item) in sections.iter().enumerate() {
+        if let BookItem::PartTitle(next) = item {
+            ranges.push((title.replace(next.clone()), start..i));
+            start = i + 1;
+        }
+    }
+    ranges.push((title, start..sections.len()));
+    ranges
+}
+
+/// Every chapter nested under an item.
+fn descendants(item: &BookItem) -> Vec<&Chapter> {
+    let mut chapters = vec![];
+    if let BookItem::Chapter(chapter) = item {
+        for item in &chapter.sub_items {
+            chapters.extend(self::chapter(item));
+            chapters.extend(descendants(item));
+        }
+    }
+    chapters
+}
+
+/// Sort the numbered chapters among `items` within each run between
+/// separators. Prefix and suffix chapters, which have no number, and
+/// separators stay where SUMMARY.md put them.
+fn sort_chapters(
+    items: &mut [BookItem],
+    order: ChapterOrder,
+    value: &impl Fn(&Chapter, &str) -> Option<String>,
+) {
+    for run in items.split_mut(|item| matches!(item, BookItem::Separator)) {
+        sort_run(run, order, value);
+    }
+}
+
+fn sort_run(
+    items: &mut [BookItem],
+    order: ChapterOrder,
+    value: &impl Fn(&Chapter, &str) -> Option<String>,
+) {
+    let slots: Vec<_> = (0..items.len())
+        .filter(|&i| chapter(&items[i]).is_some_and(|c| c.number.is_some()))
+        .collect();
+    let mut chapters: Vec<_> = slots
+        .iter()
+        .map(|&i| std::mem::replace(&mut items[i], BookItem::Separator))
+        .collect();
+
+    match order {
+        // newest first
+        ChapterOrder::Date => chapters.sort_by(|a, b| {
+            let date = |item: &BookItem| {
+                chapter(item)
+                    .and_then(|c| value(c, "date"))
+                    .and_then(|v| date::parse_date(&v))
+            };
+            last_if_none(
+                date(a).map(std::cmp::Reverse),
+                date(b).map(std::cmp::Reverse),
+            )
+        }),
+        ChapterOrder::Weight => chapters.sort_by(|a, b| {
+            let weight = |item: &BookItem| chapter(item).and_then(|c| weight(c, value));
+            last_if_none(weight(a), weight(b))
+        }),
+    }
+
+    for (slot, chapter) in slots.into_iter().zip(chapters) {
+        items[slot] = chapter;
+    }
+}
+
+/// A chapter's `weight` (or `order`) value.
+fn weight(chapter: &Chapter, value: &impl Fn(&Chapter, &str) -> Option<String>) -> Option<i64> {
+    value(chapter, "weight")
+        .or_else(|| value(chapter, "order"))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Clear the section number of `numbered: false` chapters and everything
+/// under them. Returns whether any chapter lost its number.
+pub(crate) fn unnumber(
+    items: &mut [BookItem],
+    value: &impl Fn(&Chapter, &str) -> Option<String>,
+) -> bool {
+    let mut changed = false;
+    for item in items {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+        if chapter.number.is_some()
+            && value(chapter, "numbered").is_some_and(|v| value::is_false(&v))
+        {
+            clear_numbers(chapter);
+            changed = true;
+        } else {
+            changed |= unnumber(&mut chapter.sub_items, value);
+        }
+    }
+    changed
+}
+
+fn clear_numbers(chapter: &mut Chapter) {
+    chapter.number = None;
+    for item in &mut chapter.sub_items {
+        if let BookItem::Chapter(chapter) = item {
+            clear_numbers(chapter);
+        }
+    }
+}
+
+/// Number the numbered chapters again the way mdbook does: continuously
+/// across parts at the top level, and from 1 under each parent.
+pub(crate) fn renumber(items: &mut [BookItem], parent: &SectionNumber, count: &mut u32) {
+    for item in items {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+        if chapter.number.is_none() {
+            continue;
+        }
+        *count += 1;
+        let mut number = parent.clone();
+        number.push(*count);
+        renumber(&mut chapter.sub_items, &number, &mut 0);
+        chapter.number = Some(number);
+    }
+}
+
+fn chapter(item: &BookItem) -> Option<&Chapter> {
+    match item {
+        BookItem::Chapter(chapter) => Some(chapter),
+        _ => None,
+    }
+}
+
+/// Compare two optional keys, with missing keys after everything else.
+fn last_if_none<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, number: Option<u32>, weight: Option<i64>) -> BookItem {
+        let content = weight.map_or(String::new(), |w| format!("weight: {}", w));
+        let mut chapter = Chapter::new(name, content, format!("{}.md", name), vec![]);
+        chapter.number = number.map(|n| SectionNumber(vec![n]));
+        BookItem::Chapter(chapter)
+    }
+
+    /// The weight the test chapters carry in their content.
+    fn value(chapter: &Chapter, key: &str) -> Option<String> {
+        let weight = chapter.content.strip_prefix("weight: ")?;
+        (key == "weight").then(|| weight.to_string())
+    }
+
+    fn names(items: &[BookItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| chapter(item).map_or("---", |c| c.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn weights_leave_prefix_and_suffix_chapters_alone() {
+        let mut sections = vec![
+            item("introduction", None, None),
+            item("setup", Some(1), Some(20)),
+            item("usage", Some(2), Some(10)),
+            item("faq", Some(3), None),
+            BookItem::Separator,
+            item("api", Some(4), Some(2)),
+            item("cli", Some(5), Some(1)),
+            BookItem::Separator,
+            item("glossary", None, None),
+        ];
+        assert!(sort_by_weight(&mut sections, value));
+        renumber(&mut sections, &SectionNumber::default(), &mut 0);
+        assert_eq!(
+            names(&sections),
+            [
+                "introduction",
+                "usage",
+                "setup",
+                "faq",
+                "---",
+                "cli",
+                "api",
+                "---",
+                "glossary"
+            ]
+        );
+        let numbers: Vec<_> = sections
+            .iter()
+            .filter_map(|item| chapter(item)?.number.clone())
+            .map(|number| number.0)
+            .collect();
+        assert_eq!(numbers, [[1], [2], [3], [4], [5]]);
+    }
+
+    #[test]
+    fn date_order_leaves_prefix_and_suffix_chapters_alone() {
+        let mut sections = vec![
+            item("preface", None, None),
+            BookItem::PartTitle("Blog".to_string()),
+            item("older", Some(1), Some(1)),
+            item("newer", Some(2), Some(2)),
+            item("afterword", None, None),
+        ];
+        let dates = |chapter: &Chapter, key: &str| {
+            let day = value(chapter, "weight")?;
+            (key == "date").then(|| format!("2024-01-0{}", day))
+        };
+        let order = HashMap::from([("Blog".to_string(), ChapterOrder::Date)]);
+        assert!(sort_parts(&mut sections, &order, dates));
+        assert_eq!(
+            names(&sections),
+            ["preface", "---", "newer", "older", "afterword"]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Files written next to the book as a side effect of preprocessing.
+//!
+//! Feeds, the sitemap, exports, sidecars and redirect stubs are all written
+//! into `src` with [write_if_changed]: a preprocessor runs before the html
+//! renderer empties the build directory, and the renderer copies every file
+//! from `src` into the build, so that's the one place they reach the site.
+use log::debug;
+use mdbook::errors::Error;
+use std::fs;
+use std::path::Path;
+
+/// The html file a chapter is rendered to, with `/` separators.
+pub(crate) fn html_path(path: &Path) -> String {
+    path.with_extension("html")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Absolute URL of a chapter's page.
+pub(crate) fn page_url(base_url: &str, path: &Path) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), html_path(path))
+}
+
+/// Link from one chapter's page to another's, relative so the book works
+/// under any site url.
+pub(crate) fn relative_url(from: &Path, to: &Path) -> String {
+    let depth = from.components().count().saturating_sub(1);
+    format!("{}{}", "../".repeat(depth), html_path(to))
+}
+
+/// Write `content` to `path`, creating parent directories.
+///
+/// Unchanged files are left alone so `mdbook serve` doesn't see a change and
+/// rebuild in a loop.
+pub(crate) fn write_if_changed(path: &Path, content: &str) -> Result<(), Error> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    debug!("writing {}", path.display());
+    fs::write(path, content)
+        .map_err(|e| Error::msg(format!("unable to write {}: {}", path.display(), e)))
+}
@@ -0,0 +1,528 @@
+//! Find frontmatter blocks in chapter markdown.
+use crate::config::{Config, DuplicateKeys};
+use crate::value::split_list;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::ops::Range;
+
+/// Opens and closes a frontmatter block.
+///
+/// NOTE: "---" is interpreted as Header, so use "+++"
+pub(crate) const DELIMITER: &str = "+++";
+
+/// The byte order mark editors on Windows may start a file with.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// A line of a frontmatter block: its line number in the chapter, its text.
+///
+/// The text borrows from the chapter unless pulldown split the line into
+/// several events, or unescaped it.
+type Line<'a> = (usize, Cow<'a, str>);
+
+/// A problem with a chapter's frontmatter, at a line of the chapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Diagnostic {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+/// Replace each frontmatter block in the chapter source with the text
+/// `on_block` returns for its key/values. Everything between the blocks is
+/// kept byte for byte.
+///
+/// With `sections`, the blocks below the top of the chapter are numbered
+/// from 1 as the second argument; the chapter's own block gets `None`.
+///
+/// Keys are given their canonical spelling from `key_aliases`, and a key
+/// repeated in a block is resolved by `duplicate_keys`.
+pub(crate) fn splice_frontmatter(
+    content: &str,
+    config: &Config,
+    mut on_block: impl FnMut(Vec<(String, String)>, Option<usize>) -> String,
+) -> String {
+    match try_splice_frontmatter(content, config, |frontmatter, section| {
+        Ok::<_, Infallible>(on_block(frontmatter, section))
+    }) {
+        Ok(spliced) => spliced,
+        Err(never) => match never {},
+    }
+}
+
+/// Same as [splice_frontmatter], stopping at the first error `on_block`
+/// returns.
+pub(crate) fn try_splice_frontmatter<E>(
+    content: &str,
+    config: &Config,
+    mut on_block: impl FnMut(Vec<(String, String)>, Option<usize>) -> Result<String, E>,
+) -> Result<String, E> {
+    let mut spliced = String::with_capacity(content.len());
+    let mut written = 0;
+    let mut sections = 0;
+    let mut failed = None;
+    walk(content, reads_anywhere(config), |range, top, lines| {
+        if failed.is_some() {
+            return;
+        }
+        let section = (!chapter_block(top, config)).then(|| {
+            sections += 1;
+            sections
+        });
+        match on_block(key_values(lines, config), section) {
+            Ok(block) => {
+                spliced.push_str(&content[written..range.start]);
+                spliced.push_str(&block);
+                written = range.end;
+            }
+            Err(error) => failed = Some(error),
+        }
+    });
+    if let Some(error) = failed {
+        return Err(error);
+    }
+    spliced.push_str(&content[written..]);
+    Ok(spliced)
+}
+
+/// Every key/value in a chapter's frontmatter, without rendering anything.
+/// Section blocks aren't part of it, and a key set by several blocks (with
+/// `anywhere`) is resolved by `duplicate_keys` like one set twice in a block.
+pub(crate) fn read_frontmatter(content: &str, config: &Config) -> Vec<(String, String)> {
+    let mut frontmatter = vec![];
+    walk(content, reads_anywhere(config), |_, top, lines| {
+        if chapter_block(top, config) {
+            frontmatter.extend(key_values(lines, config))
+        }
+    });
+    resolve_duplicates(frontmatter, config.duplicate_keys)
+}
+
+/// The key/values of a block's lines, duplicates resolved.
+fn key_values(lines: &[Line], config: &Config) -> Vec<(String, String)> {
+    let frontmatter = parse_frontmatter(lines, config)
+        .into_iter()
+        .map(|(_, key, value)| (key, value))
+        .collect();
+    resolve_duplicates(frontmatter, config.duplicate_keys)
+}
+
+/// The line each key is first written on.
+pub(crate) fn key_lines(content: &str, config: &Config) -> Vec<(String, usize)> {
+    let mut lines: Vec<(String, usize)> = vec![];
+    walk(content, reads_anywhere(config), |_, top, block| {
+        if !chapter_block(top, config) {
+            return;
+        }
+        for (line, key, _) in parse_frontmatter(block, config) {
+            if !lines.iter().any(|(k, _)| *k == key) {
+                lines.push((key, line));
+            }
+        }
+    });
+    lines
+}
+
+/// Byte range of each frontmatter block in a chapter, delimiters included.
+pub(crate) fn block_ranges(content: &str, config: &Config) -> Vec<Range<usize>> {
+    ranges(content, reads_anywhere(config))
+}
+
+/// Line of a `+++` that opens a block and is never closed.
+pub(crate) fn unclosed_block(content: &str, config: &Config) -> Option<usize> {
+    walk(content, reads_anywhere(config), |_, _, _| ())
+}
+
+fn ranges(content: &str, anywhere: bool) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    walk(content, anywhere, |range, _, _| ranges.push(range));
+    ranges
+}
+
+/// Byte ranges of the code blocks, fences included, and inline code in a
+/// chapter, in order.
+pub(crate) fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    pulldown_cmark::Parser::new(content)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::CodeBlock(_)) | Event::Code(_)))
+        .map(|(_, range)| range)
+        .collect()
+}
+
+/// Whether `offset` is inside one of `ranges`, as [code_ranges] returns them.
+pub(crate) fn in_ranges(ranges: &[Range<usize>], offset: usize) -> bool {
+    let next = ranges.partition_point(|range| range.end <= offset);
+    ranges.get(next).is_some_and(|range| range.start <= offset)
+}
+
+/// Tells delimiters apart from the same text in code blocks and raw html.
+/// Inline code never reaches here as text.
+#[derive(Default)]
+struct Delimiters {
+    verbatim: bool,
+}
+
+impl Delimiters {
+    /// Whether `event` is a delimiter, keeping track of what it's inside of.
+    fn is_delimiter(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock) => self.verbatim = true,
+            Event::End(TagEnd::CodeBlock | TagEnd::HtmlBlock) => self.verbatim = false,
+            Event::Text(text) => return !self.verbatim && text.trim_end_matches('\r') == DELIMITER,
+            _ => (),
+        }
+        false
+    }
+}
+
+/// Whether blocks below the top of a chapter are read at all.
+fn reads_anywhere(config: &Config) -> bool {
+    config.anywhere || config.sections
+}
+
+/// Whether a block holds the chapter's keys rather than a section's: with
+/// `sections`, only the one at the very top does.
+fn chapter_block(top: bool, config: &Config) -> bool {
+    !config.sections || top
+}
+
+/// Whether a delimiter at `offset` opens a block: anywhere with `anywhere`,
+/// otherwise only as the first thing in the chapter.
+fn opens_block(content: &str, offset: usize, blocks: usize, anywhere: bool) -> bool {
+    anywhere || (blocks == 0 && at_top(content, offset))
+}
+
+/// Whether nothing but blank lines comes before `offset`.
+fn at_top(content: &str, offset: usize) -> bool {
+    content[..offset].trim().is_empty()
+}
+
+/// Everything wrong with how a chapter's frontmatter is written: lines that
+/// aren't `key: value`, keys repeated in a block and blocks never closed.
+pub(crate) fn diagnostics(content: &str, config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let unclosed = walk(content, reads_anywhere(config), |_, _, block| {
+        for (line, text) in block {
+            if !text.trim().is_empty() && !text.contains(':') {
+                diagnostics.push(Diagnostic {
+                    line: *line,
+                    message: format!("expected \"key: value\", found {:?}", text.trim()),
+                });
+            }
+        }
+
+        let frontmatter = parse_frontmatter(block, config);
+        for (i, (line, key, _)) in frontmatter.iter().enumerate() {
+            if let Some((first, _, _)) = frontmatter[..i].iter().find(|(_, k, _)| k == key) {
+                diagnostics.push(Diagnostic {
+                    line: *line,
+                    message: format!(
+                        "{} was already set on line {}, keeping the {} value",
+                        key,
+                        first,
+                        match config.duplicate_keys {
+                            DuplicateKeys::First => "first",
+                            DuplicateKeys::Last => "last",
+                            DuplicateKeys::List => "combined",
+                        }
+                    ),
+                });
+            }
+        }
+    });
+    if !reads_anywhere(config) {
+        let top = ranges(content, false);
+        for range in ranges(content, true)
+            .iter()
+            .filter(|range| !top.contains(range))
+        {
+            diagnostics.push(Diagnostic {
+                line: line_of(content, range.start),
+                message: format!(
+                    "{:?} block below the top of the chapter is left as written, \
+                     set anywhere = true or sections = true to read it",
+                    DELIMITER
+                ),
+            });
+        }
+    }
+    if let Some(line) = unclosed {
+        diagnostics.push(Diagnostic {
+            line,
+            message: format!(
+                "frontmatter block is never closed with {:?}, it is left as written",
+                DELIMITER
+            ),
+        });
+    }
+    diagnostics
+}
+
+/// Call `on_block` with the byte range of each frontmatter block, delimiters
+/// included, whether it's at the top of the chapter and its lines as
+/// written. Only a block at the top of the chapter counts, unless
+/// `anywhere`.
+///
+/// A byte order mark in front of the chapter is skipped, the ranges still
+/// being offsets into `content`, and `\r\n` line endings read like `\n`.
+///
+/// Returns the line of a block left open at the end of the chapter.
+fn walk<'a>(
+    content: &'a str,
+    anywhere: bool,
+    mut on_block: impl FnMut(Range<usize>, bool, &[Line<'a>]),
+) -> Option<usize> {
+    let bom = if content.starts_with(BOM) {
+        BOM.len_utf8()
+    } else {
+        0
+    };
+    let content = &content[bom..];
+
+    // most chapters have no frontmatter, and needn't be parsed to tell
+    let could_open = if anywhere {
+        content.contains(DELIMITER)
+    } else {
+        content.trim_start().starts_with(DELIMITER)
+    };
+    if !could_open {
+        return None;
+    }
+    let mut lines = LineCounter::new(content);
+
+    // line and offset of the opening delimiter while capturing frontmatter
+    let mut capture = None;
+    let mut blocks = 0;
+    let mut delimiters = Delimiters::default();
+    let mut frontmatter_collection: Vec<Line<'a>> = vec![];
+
+    // loop through events to find frontmatter section based on delimiter
+    for (event, range) in pulldown_cmark::Parser::new(content).into_offset_iter() {
+        let delimiter = delimiters.is_delimiter(&event);
+        match event {
+            // handle delimiter
+            _ if delimiter
+                && (capture.is_some() || opens_block(content, range.start, blocks, anywhere)) =>
+            {
+                // first time seeing delimiter, this is None
+                // second time, hand over the captured frontmatter
+                if let Some((_, start)) = capture.take() {
+                    on_block(
+                        start + bom..range.end + bom,
+                        at_top(content, start),
+                        &frontmatter_collection,
+                    );
+                    frontmatter_collection.clear();
+                    blocks += 1;
+                } else {
+                    capture = Some((lines.at(range.start), range.start));
+                }
+            }
+            // capture content within frontmatter delimiters
+            //
+            // pulldown splits a line into several text events
+            // (e.g. around "[" and "]"), so join them per line
+            Event::Text(text) if capture.is_some() => push_text(
+                &mut frontmatter_collection,
+                borrowed(text),
+                lines.at(range.start),
+            ),
+            // inline code keeps its backticks
+            Event::Code(code) if capture.is_some() => push_text(
+                &mut frontmatter_collection,
+                Cow::Owned(format!("`{}`", code)),
+                lines.at(range.start),
+            ),
+            // a line break starts the next key/value
+            Event::SoftBreak | Event::HardBreak if capture.is_some() => {
+                frontmatter_collection.push((lines.at(range.end), Cow::Borrowed("")))
+            }
+            // everything else is left to the chapter
+            _ => (),
+        }
+    }
+
+    capture.map(|(line, _)| line)
+}
+
+/// The line of `content` a byte offset is on.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+/// Line numbers of offsets into a chapter, counted on from the last offset
+/// asked for, since events mostly come in order.
+struct LineCounter<'a> {
+    content: &'a str,
+    offset: usize,
+    line: usize,
+}
+
+impl<'a> LineCounter<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            offset: 0,
+            line: 1,
+        }
+    }
+
+    fn at(&mut self, offset: usize) -> usize {
+        if offset < self.offset {
+            *self = Self::new(self.content);
+        }
+        self.line += self.content[self.offset..offset].matches('\n').count();
+        self.offset = offset;
+        self.line
+    }
+}
+
+/// The text of an event, still borrowing from the chapter if it does.
+fn borrowed(text: CowStr) -> Cow<str> {
+    match text {
+        CowStr::Borrowed(text) => Cow::Borrowed(text),
+        text => Cow::Owned(text.into_string()),
+    }
+}
+
+/// Add text to the current line, starting it if this is its first text.
+fn push_text<'a>(lines: &mut Vec<Line<'a>>, text: Cow<'a, str>, line: usize) {
+    match lines.last_mut() {
+        Some((start, current)) if current.is_empty() => {
+            *start = line;
+            *current = text;
+        }
+        Some((_, current)) => current.to_mut().push_str(&text),
+        None => lines.push((line, text)),
+    }
+}
+
+/// Create key/values for frontmatter by splitting ":" and trimming whitespace,
+/// with the line each is on.
+///
+/// Use a `Vec` so the order is preserved.
+fn parse_frontmatter(frontmatter_text: &[Line], config: &Config) -> Vec<(usize, String, String)> {
+    frontmatter_text
+        .iter()
+        .filter_map(|(line, text)| {
+            // separate by colon + space
+            let (key, value) = text.split_once(':')?;
+            let key = config.canonical_key(key.trim());
+            Some((*line, key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Keep one entry per key, at the position of its first one.
+pub(crate) fn resolve_duplicates(
+    block: Vec<(String, String)>,
+    policy: DuplicateKeys,
+) -> Vec<(String, String)> {
+    let mut resolved: Vec<(String, String)> = Vec::with_capacity(block.len());
+    for (key, value) in block {
+        let Some((_, existing)) = resolved.iter_mut().find(|(k, _)| *k == key) else {
+            resolved.push((key, value));
+            continue;
+        };
+        match policy {
+            DuplicateKeys::First => (),
+            DuplicateKeys::Last => *existing = value,
+            DuplicateKeys::List => {
+                let mut items = split_list(existing);
+                items.extend(split_list(&value));
+                *existing = items.join(", ");
+            }
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAPTER: &str = "+++\ntitle: Setup\nowner: ops team\n+++\n\n# Setup\n";
+
+    fn frontmatter(content: &str) -> Vec<(String, String)> {
+        read_frontmatter(content, &Config::default())
+    }
+
+    fn expected() -> Vec<(String, String)> {
+        vec![
+            ("title".to_string(), "Setup".to_string()),
+            ("owner".to_string(), "ops team".to_string()),
+        ]
+    }
+
+    #[test]
+    fn reads_lf() {
+        assert_eq!(frontmatter(CHAPTER), expected());
+    }
+
+    #[test]
+    fn reads_crlf() {
+        let crlf = CHAPTER.replace('\n', "\r\n");
+        assert_eq!(frontmatter(&crlf), expected());
+        assert_eq!(
+            key_lines(&crlf, &Config::default())[1],
+            ("owner".to_string(), 3)
+        );
+    }
+
+    #[test]
+    fn reads_bom() {
+        for content in [CHAPTER.to_string(), CHAPTER.replace('\n', "\r\n")] {
+            let content = format!("{}{}", BOM, content);
+            assert_eq!(frontmatter(&content), expected());
+        }
+    }
+
+    #[test]
+    fn splice_keeps_bom_before_block() {
+        let content = format!("{}{}", BOM, CHAPTER.replace('\n', "\r\n"));
+        let ranges = block_ranges(&content, &Config::default());
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(&content[..ranges[0].start], "\u{feff}");
+        assert!(content[ranges[0].clone()].starts_with(DELIMITER));
+        assert!(content[ranges[0].clone()].ends_with(DELIMITER));
+
+        let spliced = splice_frontmatter(&content, &Config::default(), |_, _| "T".to_string());
+        assert_eq!(spliced, "\u{feff}T\r\n\r\n# Setup\r\n");
+    }
+
+    #[test]
+    fn bom_block_is_the_chapter_block_in_sections_mode() {
+        let config = Config {
+            sections: true,
+            ..Config::default()
+        };
+        let content = format!("{}{}", BOM, CHAPTER);
+        assert_eq!(read_frontmatter(&content, &config), expected());
+    }
+
+    #[test]
+    fn values_keep_no_carriage_return() {
+        let crlf = "+++\r\ntitle: Setup \r\n+++\r\n";
+        assert_eq!(frontmatter(crlf)[0].1, "Setup");
+        assert!(diagnostics(crlf, &Config::default()).is_empty());
+    }
+
+    #[test]
+    fn keys_of_several_blocks_are_resolved() {
+        let content = "+++\nowner: docs\n+++\n\nText\n\n+++\nowner: ops\nteam: a\n+++\n";
+        let mut config = Config {
+            anywhere: true,
+            ..Config::default()
+        };
+        let owner = |config: &Config| {
+            let frontmatter = read_frontmatter(content, config);
+            assert_eq!(frontmatter.len(), 2);
+            frontmatter[0].1.clone()
+        };
+        assert_eq!(owner(&config), "docs");
+        config.duplicate_keys = DuplicateKeys::Last;
+        assert_eq!(owner(&config), "ops");
+        config.duplicate_keys = DuplicateKeys::List;
+        assert_eq!(owner(&config), "docs, ops");
+    }
+}
@@ -0,0 +1,53 @@
+//! Frontmatter for backends other than html, which get the chapter as
+//! written with each block removed or turned into a plain Markdown list:
+//! text backends like `markdown` and `linkcheck`, and `epub`, whose XHTML has
+//! no stylesheet for the html table.
+use crate::config::Config;
+use crate::parse;
+use crate::table;
+
+/// Name of mdbook's markdown renderer.
+pub(crate) const RENDERER: &str = "markdown";
+
+/// Name of mdbook-epub's renderer.
+pub(crate) const EPUB_RENDERER: &str = "epub";
+
+/// Name of mdbook-linkcheck's renderer.
+pub(crate) const LINKCHECK_RENDERER: &str = "linkcheck";
+
+/// `content` with every frontmatter block removed, or with `metadata`
+/// replaced by a `- **key:** value` list.
+///
+/// The chapter's block lists `defaults` too, like the html table.
+pub(crate) fn render(
+    content: &str,
+    config: &Config,
+    defaults: &[(String, String)],
+    metadata: bool,
+) -> String {
+    let mut defaults = Some(defaults);
+    parse::splice_frontmatter(content, config, |mut frontmatter, section| {
+        if !metadata {
+            return String::new();
+        }
+        if section.is_none() {
+            frontmatter.extend(defaults.take().unwrap_or_default().iter().cloned());
+        }
+        list(frontmatter, config)
+    })
+}
+
+fn list(frontmatter: Vec<(String, String)>, config: &Config) -> String {
+    table::order_keys(frontmatter, config)
+        .iter()
+        .filter(|(_, value)| !(config.hide_empty && value.is_empty()))
+        // no raw html, which could break epub's XHTML
+        .map(|(key, value)| {
+            format!(
+                "- **{}:** {}\n",
+                table::label(key, config),
+                value.replace('<', "&lt;")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,51 @@
+//! Built-in rendering profiles for well-known frontmatter layouts.
+use serde::Deserialize;
+
+/// A preset for key order, labels and date keys.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Render keys as written.
+    #[default]
+    Default,
+    /// Rust-style RFC metadata.
+    Rfc,
+}
+
+impl Profile {
+    /// Keys rendered first, in this order.
+    pub(crate) fn key_order(self) -> &'static [&'static str] {
+        match self {
+            Profile::Default => &[],
+            Profile::Rfc => &[
+                "status",
+                "rfc",
+                "title",
+                "start-date",
+                "tracking-issue",
+                "superseded-by",
+            ],
+        }
+    }
+
+    /// Display label for a key.
+    pub(crate) fn label(self, key: &str) -> Option<&'static str> {
+        match (self, key) {
+            (Profile::Rfc, "status") => Some("Status"),
+            (Profile::Rfc, "rfc") => Some("RFC"),
+            (Profile::Rfc, "title") => Some("Title"),
+            (Profile::Rfc, "start-date") => Some("Start Date"),
+            (Profile::Rfc, "tracking-issue") => Some("Tracking Issue"),
+            (Profile::Rfc, "superseded-by") => Some("Superseded By"),
+            _ => None,
+        }
+    }
+
+    /// Keys holding `YYYY-MM-DD` dates.
+    pub(crate) fn date_keys(self) -> &'static [&'static str] {
+        match self {
+            Profile::Default => &[],
+            Profile::Rfc => &["start-date"],
+        }
+    }
+}
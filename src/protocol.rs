@@ -0,0 +1,290 @@
+//! The `[context, book]` JSON mdbook sends a preprocessor on stdin, and the
+//! book it expects back.
+//!
+//! The payload is read into this crate's own types, which only hold what
+//! every mdbook version sends, and the book goes back out in the layout it
+//! came in. Each mdbook version's layout is glue behind a cargo feature
+//! (`mdbook04`, `mdbook05`, both on by default), so one binary keeps working
+//! when mdbook changes its layout. Processing is done on mdbook 0.4's types
+//! whichever layout the book came in.
+use mdbook::book::SectionNumber;
+use mdbook::errors::Error;
+use mdbook::preprocess::PreprocessorContext;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::io;
+use std::path::PathBuf;
+
+#[cfg(not(any(feature = "mdbook04", feature = "mdbook05")))]
+compile_error!("enable at least one of the mdbook04 and mdbook05 features");
+
+/// The context a preprocessor is run in.
+#[derive(Debug, Serialize, Deserialize)]
+struct Context {
+    root: PathBuf,
+    /// `book.toml`, as mdbook serialized it.
+    config: Value,
+    renderer: String,
+    mdbook_version: String,
+}
+
+/// An entry of the book's table of contents.
+#[derive(Debug, Serialize, Deserialize)]
+enum Item {
+    Chapter(Chapter),
+    Separator,
+    PartTitle(String),
+}
+
+/// A chapter and its sub-chapters. Draft chapters have no path.
+#[derive(Debug, Serialize, Deserialize)]
+struct Chapter {
+    name: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    number: Option<Vec<u32>>,
+    #[serde(default)]
+    sub_items: Vec<Item>,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    source_path: Option<PathBuf>,
+    #[serde(default)]
+    parent_names: Vec<String>,
+}
+
+/// How the calling mdbook lays out a book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Wire {
+    /// mdbook 0.4: `{ "sections": [..], "__non_exhaustive": null }`.
+    #[cfg(feature = "mdbook04")]
+    Mdbook04,
+    /// mdbook 0.5 and later: `{ "items": [..] }`.
+    #[cfg(feature = "mdbook05")]
+    Mdbook05,
+}
+
+#[cfg(feature = "mdbook04")]
+mod mdbook04 {
+    use serde_json::{json, Value};
+
+    /// The key holding the book's items.
+    pub(super) const ITEMS: &str = "sections";
+
+    /// The mdbook versions that lay a book out this way.
+    pub(super) const VERSIONS: &str = ">=0.4.0, <0.5.0";
+
+    pub(super) fn book(items: Value) -> Value {
+        json!({ ITEMS: items, "__non_exhaustive": null })
+    }
+}
+
+#[cfg(feature = "mdbook05")]
+mod mdbook05 {
+    use serde_json::{json, Value};
+
+    /// The key holding the book's items.
+    pub(super) const ITEMS: &str = "items";
+
+    /// The mdbook versions that lay a book out this way.
+    pub(super) const VERSIONS: &str = ">=0.5.0, <0.6.0";
+
+    pub(super) fn book(items: Value) -> Value {
+        json!({ ITEMS: items })
+    }
+}
+
+impl Wire {
+    /// The layout of `book`, from the key its items are under.
+    fn detect(book: &Map<String, Value>) -> Result<Self, String> {
+        #[cfg(feature = "mdbook04")]
+        if book.contains_key(mdbook04::ITEMS) {
+            return Ok(Wire::Mdbook04);
+        }
+        #[cfg(feature = "mdbook05")]
+        if book.contains_key(mdbook05::ITEMS) {
+            return Ok(Wire::Mdbook05);
+        }
+        Err(match ["sections", "items"]
+            .iter()
+            .find(|key| book.contains_key(**key))
+        {
+            Some(&"sections") => {
+                "this build can't read mdbook 0.4 books, enable the mdbook04 feature"
+            }
+            Some(_) => "this build can't read mdbook 0.5 books, enable the mdbook05 feature",
+            None => "the book has neither sections nor items",
+        }
+        .to_string())
+    }
+
+    fn items_key(self) -> &'static str {
+        match self {
+            #[cfg(feature = "mdbook04")]
+            Wire::Mdbook04 => mdbook04::ITEMS,
+            #[cfg(feature = "mdbook05")]
+            Wire::Mdbook05 => mdbook05::ITEMS,
+        }
+    }
+
+    fn book(self, items: Value) -> Value {
+        match self {
+            #[cfg(feature = "mdbook04")]
+            Wire::Mdbook04 => mdbook04::book(items),
+            #[cfg(feature = "mdbook05")]
+            Wire::Mdbook05 => mdbook05::book(items),
+        }
+    }
+
+    /// Whether `version` of mdbook lays books out this way.
+    pub(crate) fn supports(self, version: &Version) -> bool {
+        let versions = match self {
+            #[cfg(feature = "mdbook04")]
+            Wire::Mdbook04 => mdbook04::VERSIONS,
+            #[cfg(feature = "mdbook05")]
+            Wire::Mdbook05 => mdbook05::VERSIONS,
+        };
+        VersionReq::parse(versions).is_ok_and(|versions| versions.matches(version))
+    }
+}
+
+/// The context and book in a payload, and how the book was laid out.
+pub(crate) fn read(
+    payload: &str,
+) -> Result<(PreprocessorContext, mdbook::book::Book, Wire), Error> {
+    let invalid = |message: &str| Error::msg(format!("unable to parse the input: {}", message));
+    let (ctx, book): (Context, Map<String, Value>) =
+        serde_json::from_str(payload).map_err(|e| invalid(&e.to_string()))?;
+    let wire = Wire::detect(&book).map_err(|e| invalid(&e))?;
+    let items: Vec<Item> = serde_json::from_value(book[wire.items_key()].clone())
+        .map_err(|e| invalid(&format!("book: {}", e)))?;
+
+    // mdbook's context can only be built by deserializing one
+    let ctx = serde_json::from_value(serde_json::to_value(ctx)?)
+        .map_err(|e| invalid(&format!("context: {}", e)))?;
+    let mut book = mdbook::book::Book::new();
+    book.sections = items.into_iter().map(Into::into).collect();
+    Ok((ctx, book, wire))
+}
+
+/// Write `book` laid out the way `wire` says.
+pub(crate) fn write(
+    book: &mdbook::book::Book,
+    wire: Wire,
+    output: impl io::Write,
+) -> Result<(), Error> {
+    let items: Vec<Item> = book.sections.iter().map(Into::into).collect();
+    serde_json::to_writer(output, &wire.book(json!(items)))?;
+    Ok(())
+}
+
+impl From<Item> for mdbook::BookItem {
+    fn from(item: Item) -> Self {
+        match item {
+            Item::Chapter(chapter) => mdbook::BookItem::Chapter(mdbook::book::Chapter {
+                name: chapter.name,
+                content: chapter.content,
+                number: chapter.number.map(SectionNumber),
+                sub_items: chapter.sub_items.into_iter().map(Into::into).collect(),
+                path: chapter.path,
+                source_path: chapter.source_path,
+                parent_names: chapter.parent_names,
+            }),
+            Item::Separator => mdbook::BookItem::Separator,
+            Item::PartTitle(title) => mdbook::BookItem::PartTitle(title),
+        }
+    }
+}
+
+impl From<&mdbook::BookItem> for Item {
+    fn from(item: &mdbook::BookItem) -> Self {
+        match item {
+            mdbook::BookItem::Chapter(chapter) => Item::Chapter(Chapter {
+                name: chapter.name.clone(),
+                content: chapter.content.clone(),
+                number: chapter.number.as_ref().map(|number| number.0.clone()),
+                sub_items: chapter.sub_items.iter().map(Into::into).collect(),
+                path: chapter.path.clone(),
+                source_path: chapter.source_path.clone(),
+                parent_names: chapter.parent_names.clone(),
+            }),
+            mdbook::BookItem::Separator => Item::Separator,
+            mdbook::BookItem::PartTitle(title) => Item::PartTitle(title.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAPTER: &str = r#"{"Chapter": {"name": "Intro", "content": "+++\nowner: docs\n+++\n",
+        "number": [1], "sub_items": [], "path": "intro.md", "source_path": "intro.md",
+        "parent_names": []}}"#;
+
+    fn payload(book: &str, version: &str) -> String {
+        format!(
+            r#"[{{"root": "/book", "config": {{"book": {{"title": "Book"}}}},
+                "renderer": "html", "mdbook_version": "{}"}}, {}]"#,
+            version, book
+        )
+    }
+
+    fn round_trip(payload: &str) -> (Value, Wire, String) {
+        let (ctx, book, wire) = read(payload).unwrap();
+        let mut output = vec![];
+        write(&book, wire, &mut output).unwrap();
+        (
+            serde_json::from_slice(&output).unwrap(),
+            wire,
+            ctx.mdbook_version,
+        )
+    }
+
+    #[cfg(feature = "mdbook04")]
+    #[test]
+    fn mdbook04_layout() {
+        let book = format!(
+            r#"{{"sections": [{}, "Separator"], "__non_exhaustive": null}}"#,
+            CHAPTER
+        );
+        let (book, wire, version) = round_trip(&payload(&book, "0.4.40"));
+        assert_eq!(wire, Wire::Mdbook04);
+        assert!(wire.supports(&Version::parse(&version).unwrap()));
+        assert_eq!(book["sections"][0]["Chapter"]["number"], json!([1]));
+        assert_eq!(book["sections"][1], json!("Separator"));
+        assert!(book.get("items").is_none());
+    }
+
+    #[cfg(feature = "mdbook05")]
+    #[test]
+    fn mdbook05_layout() {
+        let book = format!(r#"{{"items": [{{"PartTitle": "Part"}}, {}]}}"#, CHAPTER);
+        let (book, wire, version) = round_trip(&payload(&book, "0.5.4"));
+        assert_eq!(wire, Wire::Mdbook05);
+        assert!(wire.supports(&Version::parse(&version).unwrap()));
+        assert_eq!(
+            book,
+            json!({ "items": [
+            { "PartTitle": "Part" },
+            { "Chapter": {
+                "name": "Intro",
+                "content": "+++\nowner: docs\n+++\n",
+                "number": [1],
+                "sub_items": [],
+                "path": "intro.md",
+                "source_path": "intro.md",
+                "parent_names": [],
+            } },
+        ] })
+        );
+    }
+
+    #[test]
+    fn unknown_layout() {
+        let error = read(&payload(r#"{"chapters": []}"#, "0.6.0")).unwrap_err();
+        assert!(error.to_string().contains("neither sections nor items"));
+    }
+}
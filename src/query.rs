@@ -0,0 +1,328 @@
+//! Predicates over a chapter's keys, for listing the chapters that match.
+//!
+//! `status == "Draft"`, `tags contains rust and not draft`,
+//! `(weight >= 10 || owner != SRE)`: a key alone is true when the chapter has
+//! it, values may be quoted or bare, and `<`/`>` compare numbers as numbers
+//! and anything else as text.
+use crate::catalog::Entry;
+use crate::value::split_list;
+use mdbook::errors::Error;
+use std::cmp::Ordering;
+
+/// A parsed query.
+#[derive(Debug)]
+pub(crate) enum Query {
+    Has(String),
+    Compare(String, Op, String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// An item of a list value.
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A bare key, value or keyword.
+    Word(String),
+    /// A quoted key or value.
+    Text(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")"];
+
+impl Query {
+    pub(crate) fn parse(query: &str) -> Result<Self, Error> {
+        let invalid =
+            |message: String| Error::msg(format!("invalid query {:?}: {}", query, message));
+        let tokens = tokenize(query).map_err(invalid)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let parsed = parser.or().map_err(invalid)?;
+        match parser.tokens.get(parser.next) {
+            Some(token) => Err(invalid(format!("unexpected {}", describe(token)))),
+            None => Ok(parsed),
+        }
+    }
+
+    /// Whether a chapter matches. `title` and `path` are the chapter's title
+    /// and path when it doesn't set them as keys.
+    pub(crate) fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Self::Has(key) => value_of(entry, key).is_some(),
+            Self::Compare(key, op, expected) => match value_of(entry, key) {
+                Some(value) => compare(&value, *op, expected),
+                None => *op == Op::Ne,
+            },
+            Self::Not(query) => !query.matches(entry),
+            Self::And(left, right) => left.matches(entry) && right.matches(entry),
+            Self::Or(left, right) => left.matches(entry) || right.matches(entry),
+        }
+    }
+}
+
+fn value_of(entry: &Entry, key: &str) -> Option<String> {
+    match (entry.value(key), key) {
+        (Some(value), _) => Some(value.to_string()),
+        (None, "title") => Some(entry.title.clone()),
+        (None, "path") => Some(entry.path.to_string_lossy().replace('\\', "/")),
+        (None, _) => None,
+    }
+}
+
+fn compare(value: &str, op: Op, expected: &str) -> bool {
+    let ordering = || match (value.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(value), Ok(expected)) => value.partial_cmp(&expected),
+        _ => Some(value.cmp(expected)),
+    };
+    match op {
+        Op::Eq => value == expected,
+        Op::Ne => value != expected,
+        Op::Lt => ordering() == Some(Ordering::Less),
+        Op::Le => matches!(ordering(), Some(Ordering::Less | Ordering::Equal)),
+        Op::Gt => ordering() == Some(Ordering::Greater),
+        Op::Ge => matches!(ordering(), Some(Ordering::Greater | Ordering::Equal)),
+        Op::Contains => split_list(value).iter().any(|item| item == expected),
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = query.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, next)) if next == c => break i + 2,
+                    Some((_, '\\')) => text.extend(chars.next().map(|(_, escaped)| escaped)),
+                    Some((_, next)) => text.push(next),
+                    None => return Err(format!("quote {} is never closed", c)),
+                }
+            };
+            tokens.push(Token::Text(text));
+            rest = &rest[end..];
+        } else {
+            let end = rest
+                .find(|c: char| {
+                    c.is_whitespace()
+                        || matches!(
+                            c,
+                            '"' | '\'' | '=' | '!' | '<' | '>' | '&' | '|' | '(' | ')'
+                        )
+                })
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected {:?}", c));
+            }
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("{:?}", word),
+        Token::Text(text) => format!("\"{}\"", text),
+        Token::Symbol(symbol) => format!("{:?}", symbol),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    /// Take the next token if it is `symbol` or the keyword `word`.
+    fn eat(&mut self, symbol: &str, word: &str) -> bool {
+        let found = match self.tokens.get(self.next) {
+            Some(Token::Symbol(s)) => *s == symbol,
+            Some(Token::Word(w)) => w == word,
+            _ => false,
+        };
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.eat("||", "or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.unary()?;
+        while self.eat("&&", "and") {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, String> {
+        if self.eat("!", "not") {
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(", "") {
+            let query = self.or()?;
+            if !self.eat(")", "") {
+                return Err("\"(\" is never closed".to_string());
+            }
+            return Ok(query);
+        }
+
+        let key = self.operand("a key")?;
+        let op = match self.tokens.get(self.next) {
+            Some(Token::Symbol("==")) => Op::Eq,
+            Some(Token::Symbol("!=")) => Op::Ne,
+            Some(Token::Symbol("<")) => Op::Lt,
+            Some(Token::Symbol("<=")) => Op::Le,
+            Some(Token::Symbol(">")) => Op::Gt,
+            Some(Token::Symbol(">=")) => Op::Ge,
+            Some(Token::Word(word)) if word == "contains" => Op::Contains,
+            _ => return Ok(Query::Has(key)),
+        };
+        self.next += 1;
+        let value = self.operand("a value")?;
+        Ok(Query::Compare(key, op, value))
+    }
+
+    fn operand(&mut self, expected: &str) -> Result<String, String> {
+        match self.tokens.get(self.next) {
+            Some(Token::Word(text) | Token::Text(text)) => {
+                self.next += 1;
+                Ok(text.clone())
+            }
+            Some(token) => Err(format!("expected {}, found {}", expected, describe(token))),
+            None => Err(format!("expected {}, found the end", expected)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{entry, Catalog};
+    use crate::export::{self, ExportFormat};
+
+    fn chapters() -> Vec<Entry> {
+        vec![
+            entry(
+                "draft.md",
+                &[
+                    ("status", "Draft"),
+                    ("tags", "rust, async"),
+                    ("weight", "5"),
+                ],
+            ),
+            entry(
+                "guide/final.md",
+                &[("status", "Final"), ("owner", "SRE team"), ("weight", "20")],
+            ),
+            entry("bare.md", &[("draft", "true")]),
+        ]
+    }
+
+    /// The paths of the chapters matching `query`.
+    fn matching(query: &str) -> Vec<String> {
+        let query = Query::parse(query).unwrap();
+        chapters()
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .map(|entry| entry.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(matching("status == Draft"), ["draft.md"]);
+        assert_eq!(matching("status != Draft"), ["guide/final.md", "bare.md"]);
+        assert_eq!(matching("status == draft"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn quoted_values() {
+        assert_eq!(matching("owner == \"SRE team\""), ["guide/final.md"]);
+        assert_eq!(matching("owner == 'SRE team'"), ["guide/final.md"]);
+        assert_eq!(matching(r#"title == "say \"hi\"""#), Vec::<String>::new());
+        assert_eq!(matching("'status' == Final"), ["guide/final.md"]);
+    }
+
+    #[test]
+    fn numbers_compare_as_numbers() {
+        assert_eq!(matching("weight > 10"), ["guide/final.md"]);
+        assert_eq!(matching("weight <= 5"), ["draft.md"]);
+        assert_eq!(matching("weight >= 5 && weight < 20"), ["draft.md"]);
+    }
+
+    #[test]
+    fn keys_lists_and_boolean_operators() {
+        assert_eq!(matching("draft"), ["bare.md"]);
+        assert_eq!(matching("tags contains rust"), ["draft.md"]);
+        assert_eq!(matching("tags contains rus"), Vec::<String>::new());
+        assert_eq!(
+            matching("not draft and (weight > 10 or tags contains async)"),
+            ["draft.md", "guide/final.md"]
+        );
+        assert_eq!(matching("!(status == Draft || draft)"), ["guide/final.md"]);
+        assert_eq!(matching("path == guide/final.md"), ["guide/final.md"]);
+        assert_eq!(matching("title == bare"), ["bare.md"]);
+    }
+
+    #[test]
+    fn malformed_queries_are_errors() {
+        for (query, message) in [
+            ("", "expected a key, found the end"),
+            ("status ==", "expected a value, found the end"),
+            ("== Draft", "expected a key, found \"==\""),
+            ("status = Draft", "unexpected '='"),
+            ("status == Draft extra", "unexpected \"extra\""),
+            ("(draft", "\"(\" is never closed"),
+            ("draft and", "expected a key, found the end"),
+            ("owner == \"SRE", "quote \" is never closed"),
+            ("draft)", "unexpected \")\""),
+        ] {
+            let error = Query::parse(query).unwrap_err().to_string();
+            assert_eq!(
+                error,
+                format!("invalid query {:?}: {}", query, message),
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn paths_format_lists_the_matches() {
+        let query = Query::parse("weight").unwrap();
+        let mut catalog = Catalog {
+            entries: chapters(),
+        };
+        catalog.entries.retain(|entry| query.matches(entry));
+        assert_eq!(
+            export::render(&catalog, ExportFormat::Paths).unwrap(),
+            "draft.md\nguide/final.md\n"
+        );
+    }
+}
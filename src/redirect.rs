@@ -0,0 +1,96 @@
+//! Redirect stubs for old chapter paths listed under `aliases`.
+//!
+//! A preprocessor can't add to `output.html.redirect`, so the stubs are
+//! written into the source directory, which the html renderer copies into the
+//! build as-is.
+use crate::output;
+use crate::value::split_list;
+use log::warn;
+use mdbook::book::Chapter;
+use mdbook::errors::Error;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Marks stubs written by the preprocessor, so other files are never replaced.
+const MARKER: &str = "<!-- generated by the frontmatter preprocessor from `aliases` -->";
+
+/// Redirects collected for every chapter.
+#[derive(Default)]
+pub(crate) struct Redirects {
+    /// Stub path relative to the source dir, and the chapter html path.
+    stubs: Vec<(PathBuf, String)>,
+}
+
+impl Redirects {
+    /// Collect the `aliases` of a chapter.
+    pub(crate) fn collect(&mut self, chapter: &Chapter, frontmatter: &[(String, String)]) {
+        let Some(path) = &chapter.path else { return };
+        let target = output::html_path(path);
+
+        for (_, aliases) in frontmatter.iter().filter(|(key, _)| key == "aliases") {
+            for alias in split_list(aliases) {
+                let stub = stub_path(&alias);
+                // stubs must stay inside the source directory
+                if stub.components().any(|c| c == Component::ParentDir) {
+                    warn!("ignoring alias {} outside the book", alias);
+                    continue;
+                }
+                self.stubs.push((stub, target.clone()));
+            }
+        }
+    }
+
+    /// Write a stub page for every alias into `src_dir`.
+    pub(crate) fn write(&self, src_dir: &Path) -> Result<(), Error> {
+        for (stub, target) in &self.stubs {
+            let path = src_dir.join(stub);
+            if fs::read_to_string(&path).is_ok_and(|existing| !existing.contains(MARKER)) {
+                warn!(
+                    "not writing a redirect to {} over existing file {}",
+                    target,
+                    path.display()
+                );
+                continue;
+            }
+
+            // relative, so the book works under any site url
+            let depth = stub.components().count().saturating_sub(1);
+            let url = format!("{}{}", "../".repeat(depth), target);
+            output::write_if_changed(&path, &stub_page(&url))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the stub for an alias lives: `/old/page.html` and `old/page.md` become
+/// `old/page.html`, `/old/dir/` becomes `old/dir/index.html`.
+fn stub_path(alias: &str) -> PathBuf {
+    let alias = alias.trim().trim_start_matches('/');
+    let mut path = PathBuf::from(alias);
+    if alias.is_empty() || alias.ends_with('/') {
+        path.push("index.html");
+    } else if path.extension().is_none() || path.extension().is_some_and(|ext| ext == "md") {
+        path.set_extension("html");
+    }
+    path
+}
+
+/// Same shape as the stubs mdbook writes for `output.html.redirect`.
+fn stub_page(url: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+{MARKER}
+<html lang="en">
+    <head>
+        <meta charset="utf-8">
+        <title>Redirecting...</title>
+        <meta http-equiv="refresh" content="0; URL={url}">
+        <link rel="canonical" href="{url}">
+    </head>
+    <body>
+        <p>Redirecting to... <a href="{url}">{url}</a>.</p>
+    </body>
+</html>
+"#
+    )
+}
@@ -0,0 +1,80 @@
+//! "Related" links between chapters that share tags.
+use crate::catalog::{Catalog, Entry};
+use crate::head::escape;
+use crate::output;
+use mdbook::book::Chapter;
+
+/// The "Related" list for a chapter: up to `limit` other chapters, those
+/// sharing the most `tags` first, then in book order.
+pub(crate) fn related(catalog: &Catalog, chapter: &Chapter, limit: usize) -> Option<String> {
+    let entry = catalog.get(chapter)?;
+    // tags match case-insensitively, as on the tag pages
+    let lowercase = |entry: &Entry| -> Vec<String> {
+        entry
+            .list("tags")
+            .iter()
+            .map(|t| t.to_lowercase())
+            .collect()
+    };
+    let tags = lowercase(entry);
+    if tags.is_empty() {
+        return None;
+    }
+
+    let mut scored: Vec<(usize, &Entry)> = catalog
+        .entries
+        .iter()
+        .filter(|other| other.path != entry.path)
+        .map(|other| {
+            let shared = lowercase(other).iter().filter(|t| tags.contains(t)).count();
+            (shared, other)
+        })
+        .filter(|(shared, _)| *shared > 0)
+        .collect();
+    if scored.is_empty() {
+        return None;
+    }
+    scored.sort_by_key(|(shared, _)| std::cmp::Reverse(*shared));
+
+    let items: String = scored
+        .iter()
+        .take(limit)
+        .map(|(_, other)| {
+            format!(
+                "<li><a href=\"{}\">{}</a></li>",
+                output::relative_url(&entry.path, &other.path),
+                escape(&other.title)
+            )
+        })
+        .collect();
+    Some(format!(
+        "\n\n<aside class=\"fm-related\">\n<h2>Related</h2>\n<ul>{}</ul>\n</aside>\n",
+        items
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::entry;
+
+    #[test]
+    fn tags_are_shared_regardless_of_case() {
+        let catalog = Catalog {
+            entries: vec![
+                entry("rust.md", &[("title", "Rust"), ("tags", "Rust, CLI")]),
+                entry("cargo.md", &[("title", "Cargo"), ("tags", "rust, cli")]),
+                entry("go.md", &[("title", "Go"), ("tags", "cli")]),
+                entry("css.md", &[("title", "CSS"), ("tags", "web")]),
+            ],
+        };
+        let chapter = Chapter::new("Rust", String::new(), "rust.md", vec![]);
+        let related = related(&catalog, &chapter, 5).unwrap();
+        assert!(
+            related.contains("<ul><li><a href=\"cargo.html\">Cargo</a></li><li><a href=\"go.html\">Go</a></li></ul>"),
+            "{}",
+            related
+        );
+        assert!(!related.contains("CSS"));
+    }
+}
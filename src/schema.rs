@@ -0,0 +1,388 @@
+//! Validation of chapters' frontmatter against a JSON Schema.
+//!
+//! Frontmatter values are text, so a value is checked as the type its
+//! property schema asks for: `"integer"` values must parse as one, `"array"`
+//! values are split like list keys and each item checked against `items`.
+//! The supported keywords are `type`, `enum`, `const`, `pattern`, `format`
+//! (`date`, `date-time`, `email`, `uri`), `minLength`, `maxLength`,
+//! `minimum`, `maximum`, `items`, `minItems`, `maxItems`, and at the top
+//! level `properties`, `required` and `additionalProperties: false`.
+//! Annotations like `title` and `description` are allowed anywhere; any
+//! other keyword (`$ref`, `allOf`, nested objects ..) is reported when the
+//! schema is loaded, since it would check nothing.
+use crate::validate;
+use crate::value::{is_false, is_true, split_list};
+use chrono::{DateTime, NaiveDate};
+use mdbook::errors::Error;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Keywords that only describe, and are allowed anywhere.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+/// Keywords checked at the top level.
+const TOP_KEYWORDS: &[&str] = &["type", "properties", "required", "additionalProperties"];
+
+/// Keywords checked in a property's (or an array's `items`) schema.
+const VALUE_KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "pattern",
+    "format",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+    "items",
+    "minItems",
+    "maxItems",
+];
+
+/// A loaded schema.
+#[derive(Debug)]
+pub(crate) struct Schema {
+    root: Value,
+    /// Every `pattern`, compiled once.
+    patterns: HashMap<String, Regex>,
+    /// What the schema asks for that isn't checked, e.g.
+    /// `/properties/owner: "$ref" isn't supported`.
+    unsupported: Vec<String>,
+}
+
+impl Schema {
+    /// Read the schema at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| Error::msg(format!("failed to read schema {:?}: {}", path, e)))?;
+        let root: Value = serde_json::from_str(&text)
+            .map_err(|e| Error::msg(format!("invalid schema {:?}: {}", path, e)))?;
+        if !root.is_object() {
+            return Err(Error::msg(format!(
+                "invalid schema {:?}: not an object",
+                path
+            )));
+        }
+        let mut schema = Self {
+            root: Value::Null,
+            patterns: HashMap::new(),
+            unsupported: vec![],
+        };
+        schema.audit(&root, "", TOP_KEYWORDS);
+        if let Some(properties) = root.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                schema
+                    .audit_value(property, &format!("/properties/{}", key))
+                    .map_err(|e| Error::msg(format!("invalid schema {:?}: {}", path, e)))?;
+            }
+        }
+        schema.root = root;
+        Ok(schema)
+    }
+
+    /// Record the keywords of `schema` (at `at`) that aren't in `known`.
+    fn audit(&mut self, schema: &Value, at: &str, known: &[&str]) {
+        let Some(schema) = schema.as_object() else {
+            self.unsupported.push(format!(
+                "{}: {} isn't a schema object",
+                at_or_root(at),
+                schema
+            ));
+            return;
+        };
+        for keyword in schema.keys() {
+            if !known.contains(&keyword.as_str()) && !ANNOTATIONS.contains(&keyword.as_str()) {
+                self.unsupported
+                    .push(format!("{}: {:?} isn't supported", at_or_root(at), keyword));
+            }
+        }
+        if let Some(additional) = schema.get("additionalProperties") {
+            if known == TOP_KEYWORDS && additional != &Value::Bool(false) {
+                self.unsupported.push(format!(
+                    "{}: only \"additionalProperties\": false is supported",
+                    at_or_root(at)
+                ));
+            }
+        }
+    }
+
+    /// Audit a property's (or `items`') schema and compile its pattern.
+    fn audit_value(&mut self, schema: &Value, at: &str) -> Result<(), String> {
+        self.audit(schema, at, VALUE_KEYWORDS);
+        if schema.get("type").and_then(Value::as_str) == Some("object") {
+            self.unsupported
+                .push(format!("{}: nested objects aren't supported", at));
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            let regex = Regex::new(pattern)
+                .map_err(|e| format!("{}: invalid pattern {:?}: {}", at, pattern, e))?;
+            self.patterns.insert(pattern.to_string(), regex);
+        }
+        if let Some(items) = schema.get("items") {
+            self.audit_value(items, &format!("{}/items", at))?;
+        }
+        Ok(())
+    }
+
+    /// What the schema asks for that isn't checked.
+    pub(crate) fn unsupported(&self) -> &[String] {
+        &self.unsupported
+    }
+
+    /// The keys the schema declares in `properties`.
+    pub(crate) fn properties(&self) -> impl Iterator<Item = &str> {
+        self.root
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|properties| properties.keys().map(String::as_str))
+    }
+
+    /// Whether the schema rejects keys it doesn't declare.
+    pub(crate) fn denies_additional(&self) -> bool {
+        self.root.get("additionalProperties") == Some(&Value::Bool(false))
+    }
+
+    /// Every violation in a chapter's keys, with the key it's about.
+    pub(crate) fn violations(
+        &self,
+        frontmatter: &[(String, String)],
+    ) -> Vec<(Option<String>, String)> {
+        let mut violations = vec![];
+        for required in self
+            .root
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !frontmatter.iter().any(|(key, _)| key == required) {
+                violations.push((None, format!("missing required {}", required)));
+            }
+        }
+
+        let properties = self.root.get("properties").and_then(Value::as_object);
+        for (key, value) in frontmatter {
+            if let Some(schema) = properties.and_then(|properties| properties.get(key)) {
+                if let Err(message) = check(value, schema, &self.patterns) {
+                    violations.push((Some(key.clone()), format!("{}: {}", key, message)));
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Check one value against its property schema, with `patterns` compiled.
+fn check(value: &str, schema: &Value, patterns: &HashMap<String, Regex>) -> Result<(), String> {
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+        return Err(format!("{:?} is not of type {}", value, types.join(" or ")));
+    }
+
+    if types.contains(&"array") {
+        let items = split_list(value);
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                return Err(format!("needs at least {} items", min));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                return Err(format!("allows at most {} items", max));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for item in &items {
+                check(item, item_schema, patterns)?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.iter().any(|option| matches(value, option)) {
+            return Err(format!(
+                "{:?} is not one of {}",
+                value,
+                options
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if !matches(value, constant) {
+            return Err(format!("{:?} is not {}", value, constant));
+        }
+    }
+
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        if patterns
+            .get(pattern)
+            .is_some_and(|regex| !regex.is_match(value))
+        {
+            return Err(format!("{:?} doesn't match {:?}", value, pattern));
+        }
+    }
+    if let Some(format) = schema.get("format").and_then(Value::as_str) {
+        if !is_format(value, format) {
+            return Err(format!("{:?} is not a valid {}", value, format));
+        }
+    }
+
+    let length = value.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+        if length < min {
+            return Err(format!("is shorter than {} characters", min));
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+        if length > max {
+            return Err(format!("is longer than {} characters", max));
+        }
+    }
+
+    if let Ok(number) = value.trim().parse::<f64>() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < min {
+                return Err(format!("{} is less than {}", value, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > max {
+                return Err(format!("{} is greater than {}", value, max));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A JSON pointer into the schema, `/` for its root.
+fn at_or_root(at: &str) -> &str {
+    if at.is_empty() {
+        "/"
+    } else {
+        at
+    }
+}
+
+fn is_type(value: &str, schema_type: &str) -> bool {
+    let value = value.trim();
+    match schema_type {
+        "integer" => value.parse::<i64>().is_ok(),
+        "number" => value.parse::<f64>().is_ok(),
+        "boolean" => is_true(value) || is_false(value),
+        "null" => value.is_empty(),
+        // text and lists can be written any way
+        _ => true,
+    }
+}
+
+/// Whether a value equals an `enum`/`const` entry, compared as text.
+fn matches(value: &str, option: &Value) -> bool {
+    match option {
+        Value::String(option) => option == value,
+        Value::Bool(option) => (if *option { is_true } else { is_false })(value),
+        Value::Null => value.is_empty(),
+        Value::Number(option) => value.trim().parse::<f64>().ok() == option.as_f64(),
+        _ => false,
+    }
+}
+
+fn is_format(value: &str, format: &str) -> bool {
+    match format {
+        "date" => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        "date-time" => DateTime::parse_from_rfc3339(value).is_ok(),
+        "email" => validate::is_email(value),
+        "uri" | "url" => validate::is_url(value),
+        // unknown formats are annotations only
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn load(schema: Value) -> Result<Schema, Error> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frontmatter.schema.json");
+        fs::write(&path, schema.to_string()).unwrap();
+        Schema::load(&path)
+    }
+
+    #[test]
+    fn reports_unsupported_keywords() {
+        let schema = load(json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Chapter",
+            "allOf": [],
+            "properties": {
+                "owner": { "$ref": "#/$defs/team", "description": "Who" },
+                "links": { "type": "array", "items": { "anyOf": [] } },
+                "seo": { "type": "object", "properties": {} },
+                "status": { "enum": ["Draft", "Final"] },
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            schema.unsupported(),
+            [
+                "/: \"allOf\" isn't supported",
+                "/properties/links/items: \"anyOf\" isn't supported",
+                "/properties/owner: \"$ref\" isn't supported",
+                "/properties/seo: \"properties\" isn't supported",
+                "/properties/seo: nested objects aren't supported",
+            ]
+        );
+    }
+
+    #[test]
+    fn compiles_patterns_once() {
+        let schema = load(json!({ "properties": {
+                "tags": { "type": "array", "items": { "pattern": "^[a-z-]+$" } },
+            } }))
+        .unwrap();
+        assert!(schema.unsupported().is_empty());
+        assert_eq!(schema.patterns.len(), 1);
+        let frontmatter = [("tags".to_string(), "rust, Async".to_string())];
+        assert_eq!(
+            schema.violations(&frontmatter),
+            [(
+                Some("tags".to_string()),
+                "tags: \"Async\" doesn't match \"^[a-z-]+$\"".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_pattern_fails_to_load() {
+        let error = load(json!({ "properties": { "id": { "pattern": "(" } } })).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("/properties/id: invalid pattern"));
+    }
+}
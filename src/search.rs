@@ -0,0 +1,34 @@
+//! Frontmatter terms for the html renderer's search index.
+use crate::config::Config;
+use crate::head::escape;
+use crate::value::split_list;
+
+/// A hidden block with the values of the `search_terms_keys`.
+///
+/// The search indexer reads the text of html blocks, so the chapter is found
+/// by its keywords and tags even when the prose never mentions them. Browsers
+/// and screen readers skip it.
+pub(crate) fn terms_block(frontmatter: &[(String, String)], config: &Config) -> Option<String> {
+    let mut terms: Vec<String> = vec![];
+    for key in &config.search_terms_keys {
+        for (_, value) in frontmatter.iter().filter(|(k, _)| k == key) {
+            for term in split_list(value) {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+        }
+    }
+    if terms.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "<div class=\"fm-search-terms\" hidden>{}</div>\n\n",
+        terms
+            .iter()
+            .map(|term| escape(term))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ))
+}
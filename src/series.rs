@@ -0,0 +1,103 @@
+//! Navigation between chapters of the same `series`.
+use crate::catalog::{Catalog, Entry};
+use crate::head::escape;
+use crate::output;
+use mdbook::book::Chapter;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Every series in the book, in reading order.
+pub(crate) struct Series<'c> {
+    /// Members of each series.
+    series: HashMap<&'c str, Vec<&'c Entry>>,
+    /// Series name and position, per chapter path.
+    chapters: HashMap<&'c PathBuf, (&'c str, usize)>,
+}
+
+impl<'c> Series<'c> {
+    /// Group chapters by `series`, ordered by `series_index` and then by their
+    /// place in the book.
+    pub(crate) fn new(catalog: &'c Catalog) -> Self {
+        let mut series: HashMap<&str, Vec<&Entry>> = HashMap::new();
+        for entry in &catalog.entries {
+            if let Some(name) = entry.value("series") {
+                series.entry(name).or_default().push(entry);
+            }
+        }
+
+        let mut chapters = HashMap::new();
+        for (name, members) in &mut series {
+            // unindexed chapters follow the indexed ones; the sort is stable
+            let index = |entry: &Entry| -> Option<i64> {
+                entry
+                    .value("series_index")
+                    .and_then(|v| v.trim().parse().ok())
+            };
+            members.sort_by_key(|entry| (index(entry).is_none(), index(entry)));
+            for (i, entry) in members.iter().enumerate() {
+                chapters.insert(&entry.path, (*name, i));
+            }
+        }
+
+        Self { series, chapters }
+    }
+
+    /// The navigation block for a chapter, if it is in a series.
+    pub(crate) fn nav(&self, chapter: &Chapter) -> Option<String> {
+        let path = chapter.path.as_ref()?;
+        let (name, i) = *self.chapters.get(path)?;
+        let members = &self.series[name];
+
+        let link = |to: &Entry, text: &str| {
+            format!(
+                "<a href=\"{}\" title=\"{}\">{}</a>",
+                output::relative_url(path, &to.path),
+                escape(&to.title),
+                text
+            )
+        };
+        let mut links = vec![];
+        if let Some(previous) = i.checked_sub(1).map(|i| members[i]) {
+            links.push(link(previous, "← previous"));
+        }
+        if let Some(next) = members.get(i + 1) {
+            links.push(link(next, "next →"));
+        }
+
+        let mut nav = format!(
+            "<nav class=\"fm-series\">Part {} of {} in <em>{}</em>",
+            i + 1,
+            members.len(),
+            escape(name)
+        );
+        if !links.is_empty() {
+            nav.push_str(" — ");
+            nav.push_str(&links.join(" | "));
+        }
+        nav.push_str("</nav>\n\n");
+        Some(nav)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::entry;
+
+    #[test]
+    fn series_name_is_escaped() {
+        let catalog = Catalog {
+            entries: vec![
+                entry("one.md", &[("series", "Tips & <Tricks>")]),
+                entry("two.md", &[("series", "Tips & <Tricks>")]),
+            ],
+        };
+        let chapter = Chapter::new("One", String::new(), "one.md", vec![]);
+        let nav = Series::new(&catalog).nav(&chapter).unwrap();
+        assert!(
+            nav.contains("Part 1 of 2 in <em>Tips &amp; &lt;Tricks&gt;</em>"),
+            "{}",
+            nav
+        );
+    }
+}
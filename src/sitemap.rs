@@ -0,0 +1,106 @@
+//! `sitemap.xml` for the book, written into the source directory like the
+//! feeds.
+use crate::catalog::Catalog;
+use crate::config::Config;
+use crate::date;
+use crate::feed::escape;
+use crate::output;
+use crate::value::is_true;
+use mdbook::errors::Error;
+use std::path::Path;
+
+/// Write a sitemap of every chapter to `<src_dir>/<sitemap_path>`.
+///
+/// `<lastmod>` is the chapter's `updated` (or `date`) key and `<priority>` its
+/// `sitemap_priority` key. `noindex` chapters are left out.
+pub(crate) fn write(
+    catalog: &Catalog,
+    config: &Config,
+    base_url: &str,
+    src_dir: &Path,
+) -> Result<(), Error> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for entry in &catalog.entries {
+        if entry.value("noindex").is_some_and(is_true) {
+            continue;
+        }
+        xml.push_str("<url>\n");
+        xml.push_str(&format!(
+            "<loc>{}</loc>\n",
+            escape(&output::page_url(base_url, &entry.path))
+        ));
+        if let Some(lastmod) = entry
+            .value("updated")
+            .or_else(|| entry.value("date"))
+            .and_then(date::parse_date)
+        {
+            xml.push_str(&format!(
+                "<lastmod>{}</lastmod>\n",
+                lastmod.format("%Y-%m-%d")
+            ));
+        }
+        if let Some(priority) = entry
+            .value("sitemap_priority")
+            .and_then(|p| p.trim().parse::<f32>().ok())
+            .filter(|p| (0.0..=1.0).contains(p))
+        {
+            xml.push_str(&format!("<priority>{:.1}</priority>\n", priority));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+
+    output::write_if_changed(&src_dir.join(&config.sitemap_path), &xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::entry;
+    use std::fs;
+
+    #[test]
+    fn sitemap_lists_indexed_chapters() {
+        let catalog = Catalog {
+            entries: vec![
+                entry(
+                    "guide/a&b.md",
+                    &[("date", "2024-01-05"), ("updated", "2024-03-01T10:00:00Z")],
+                ),
+                entry(
+                    "dated.md",
+                    &[("date", "2024-02-10"), ("sitemap_priority", "0.8")],
+                ),
+                entry("hidden.md", &[("noindex", "true")]),
+                entry("plain.md", &[("sitemap_priority", "3")]),
+            ],
+        };
+        let src = tempfile::tempdir().unwrap();
+        write(
+            &catalog,
+            &Config::default(),
+            "https://example.com/",
+            src.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(src.path().join("sitemap.xml")).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+             <url>\n\
+             <loc>https://example.com/guide/a&amp;b.html</loc>\n\
+             <lastmod>2024-03-01</lastmod>\n\
+             </url>\n\
+             <url>\n\
+             <loc>https://example.com/dated.html</loc>\n\
+             <lastmod>2024-02-10</lastmod>\n\
+             <priority>0.8</priority>\n\
+             </url>\n\
+             <url>\n\
+             <loc>https://example.com/plain.html</loc>\n\
+             </url>\n\
+             </urlset>\n"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! What a run did, logged at the end so chapters lacking metadata stand out.
+use log::info;
+use std::collections::BTreeMap;
+
+/// Counts over every chapter processed.
+#[derive(Debug, Default)]
+pub(crate) struct Summary {
+    chapters: usize,
+    blocks: usize,
+    without_frontmatter: usize,
+    /// Chapters writing each key.
+    keys: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    /// Count a chapter with its own keys and the frontmatter blocks rendered.
+    pub(crate) fn chapter(&mut self, frontmatter: &[(String, String)], blocks: usize) {
+        self.chapters += 1;
+        self.blocks += blocks;
+        if frontmatter.is_empty() {
+            self.without_frontmatter += 1;
+        }
+        let mut seen: Vec<&str> = vec![];
+        for (key, _) in frontmatter {
+            if !seen.contains(&key.as_str()) {
+                seen.push(key);
+                *self.keys.entry(key.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Log the summary, with the per-key counts when `verbose`.
+    pub(crate) fn log(&self, warnings: usize, verbose: bool) {
+        info!(
+            "{} chapters scanned, {} frontmatter blocks rendered, {} without frontmatter, {} warnings",
+            self.chapters, self.blocks, self.without_frontmatter, warnings
+        );
+        if verbose {
+            let mut keys: Vec<_> = self.keys.iter().collect();
+            keys.sort_by(|(_, a), (_, b)| b.cmp(a));
+            for (key, count) in keys {
+                info!("  {}: {} of {} chapters", key, count, self.chapters);
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Render parsed frontmatter as an html table.
+use crate::config::Config;
+use crate::date;
+use crate::error::FrontmatterError;
+use crate::i18n;
+use crate::linkify::Linkifier;
+use crate::output;
+use crate::taxonomy;
+use crate::value::{author_name, split_list};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Render frontmatter as an html table, to splice into the chapter in place
+/// of its block.
+pub(crate) fn create_html_table(
+    frontmatter: Vec<(String, String)>,
+    config: &Config,
+    linkifier: &Linkifier,
+    path: &Path,
+) -> Result<String, FrontmatterError> {
+    table(frontmatter, config, linkifier, path, "fm-")
+}
+
+/// Render a section block's table, its row ids numbered by `section` so
+/// they stay apart from the chapter's, e.g. `fm-2-owner`.
+pub(crate) fn create_section_table(
+    frontmatter: Vec<(String, String)>,
+    config: &Config,
+    linkifier: &Linkifier,
+    path: &Path,
+    section: usize,
+) -> Result<String, FrontmatterError> {
+    table(
+        frontmatter,
+        config,
+        linkifier,
+        path,
+        &format!("fm-{}-", section),
+    )
+}
+
+/// The table html, with row ids of `id_prefix` and the key.
+fn table(
+    frontmatter: Vec<(String, String)>,
+    config: &Config,
+    linkifier: &Linkifier,
+    path: &Path,
+    id_prefix: &str,
+) -> Result<String, FrontmatterError> {
+    let mut frontmatter = order_keys(frontmatter, config);
+    if config.hide_empty {
+        frontmatter.retain(|(_, value)| !value.is_empty());
+        if frontmatter.is_empty() {
+            return Ok(String::new());
+        }
+    }
+
+    // create table
+    let mut html = if config.accessible_table {
+        format!(
+            "<table class=\"preamble\">\n<caption class=\"fm-caption\">{}</caption>\n<tbody>\n",
+            config.table_caption
+        )
+    } else {
+        "<table class=\"preamble\">\n".to_string()
+    };
+    // header cells name their row for screen readers
+    let th = if config.accessible_table {
+        "<th scope=\"row\">"
+    } else {
+        "<th>"
+    };
+    // loop through frontmatter to create table rows
+    for (key, value) in frontmatter {
+        // create links for github/email and configured rules
+        let value = if config.list_keys.contains(&key) {
+            // linkify each item on its own so formatting stays clean
+            let items: Vec<_> = split_list(&value)
+                .iter()
+                .map(|item| {
+                    if config.author_page && taxonomy::AUTHOR_KEYS.contains(&key.as_str()) {
+                        // the name links to the authors page, the rest as usual
+                        let name = author_name(item);
+                        let rest = &item[item.find(name).unwrap_or(0) + name.len()..];
+                        format!(
+                            "<a class=\"fm-author\" href=\"{}\">{}</a>{}",
+                            taxonomy::author_url(config, path, name),
+                            name,
+                            linkifier.linkify(&key, rest)
+                        )
+                    } else {
+                        linkifier.linkify(&key, item)
+                    }
+                })
+                .collect();
+            if items.len() > 1 {
+                format!(
+                    "<ul class=\"fm-list\">{}</ul>",
+                    items
+                        .iter()
+                        .map(|item| format!("<li>{}</li>", item))
+                        .collect::<String>()
+                )
+            } else {
+                items.concat()
+            }
+        } else if config.tag_pages && key == "tags" {
+            // chips linking to the generated tag pages
+            split_list(&value)
+                .iter()
+                .map(|tag| {
+                    format!(
+                        "<a class=\"fm-tag\" href=\"{}\">{}</a>",
+                        output::relative_url(path, &taxonomy::tag_path(config, tag)),
+                        tag
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else if config.status_badges && key == "status" {
+            status_badge(&value, config)
+        } else if config.is_date_key(&key) {
+            date::render_date(
+                &value,
+                config.date_format_for(&key),
+                config.date_display,
+                config.language.as_deref(),
+            )
+        } else {
+            linkifier.linkify(&key, &value)
+        };
+
+        // rows can be linked to, e.g. `chapter.html#fm-status`
+        let id = format!("{}{}", id_prefix, slug(&key));
+        // the row goes straight into the table's buffer
+        write!(html, "<tr id=\"{}\">{}{}", id, th, label(&key, config))?;
+        if config.row_anchors {
+            write!(
+                html,
+                " <a class=\"fm-anchor\" href=\"#{}\" aria-label=\"Link to this row\">#</a>",
+                id
+            )?;
+        }
+        writeln!(html, "</th><td>{}</td></tr>", value)?;
+    }
+    // close table, the blank line ends the html block
+    if config.accessible_table {
+        html.push_str("</tbody>\n");
+    }
+    html.push_str("</table>\n\n");
+    Ok(html)
+}
+
+/// Render a part's overview: its title and a table of its keys.
+pub(crate) fn create_part_overview(
+    title: &str,
+    keys: Vec<(String, String)>,
+    config: &Config,
+    linkifier: &Linkifier,
+    path: &Path,
+) -> Result<String, FrontmatterError> {
+    // the chapter's own table may have the same keys
+    Ok(format!(
+        "<div class=\"fm-part\">\n<p class=\"fm-part-title\">{}</p>\n\n{}</div>\n\n",
+        title,
+        table(keys, config, linkifier, path, "fm-part-")?
+    ))
+}
+
+/// Render a status value as a badge.
+///
+/// The badge always gets an `fm-status-<value>` class; a configured (or
+/// default) color is applied inline so it works without extra CSS.
+fn status_badge(status: &str, config: &Config) -> String {
+    let status_slug = slug(status);
+    let color = config
+        .status_colors
+        .iter()
+        .find(|(value, _)| slug(value) == status_slug)
+        .map(|(_, color)| color.as_str())
+        .or_else(|| default_status_color(&status_slug));
+
+    match color {
+        Some(color) => format!(
+            "<span class=\"fm-badge fm-status-{}\" style=\"background-color: {}; color: #fff; \
+             padding: 0 0.5em; border-radius: 0.5em;\">{}</span>",
+            status_slug, color, status
+        ),
+        None => format!(
+            "<span class=\"fm-badge fm-status-{}\">{}</span>",
+            status_slug, status
+        ),
+    }
+}
+
+/// Colors for the usual document lifecycle.
+fn default_status_color(slug: &str) -> Option<&'static str> {
+    match slug {
+        "draft" => Some("#6c757d"),
+        "review" => Some("#d29922"),
+        "final" => Some("#2da44e"),
+        "deprecated" => Some("#cf222e"),
+        _ => None,
+    }
+}
+
+/// Lowercase a value and replace anything but ascii letters and digits with
+/// "-", for use in classes and ids.
+pub(crate) fn slug(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Put keys from `key_order` (or the profile's order) first.
+///
+/// The sort is stable, so every other key keeps its written order.
+pub(crate) fn order_keys(
+    mut frontmatter: Vec<(String, String)>,
+    config: &Config,
+) -> Vec<(String, String)> {
+    let position = |key: &str| -> Option<usize> {
+        if config.key_order.is_empty() {
+            config.profile.key_order().iter().position(|k| *k == key)
+        } else {
+            config.key_order.iter().position(|k| k == key)
+        }
+    };
+    frontmatter.sort_by_key(|(key, _)| position(key).unwrap_or(usize::MAX));
+    frontmatter
+}
+
+/// Header text for a key.
+///
+/// Configured labels win, then the labels for the book's language (from
+/// config, then built in), then the profile's label, then the key itself.
+pub(crate) fn label<'k>(key: &'k str, config: &'k Config) -> &'k str {
+    let translated = || {
+        let language = config.language.as_deref()?;
+        i18n::candidates(language).find_map(|language| {
+            config
+                .i18n
+                .get(language)
+                .and_then(|labels| labels.get(key))
+                .map(String::as_str)
+                .or_else(|| i18n::builtin_label(language, key))
+        })
+    };
+
+    config
+        .labels
+        .get(key)
+        .map(String::as_str)
+        .or_else(translated)
+        .or_else(|| config.profile.label(key))
+        .unwrap_or(key)
+}
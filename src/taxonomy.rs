@@ -0,0 +1,322 @@
+//! Generated pages that list chapters by their `tags`, authors or
+//! `category`.
+use crate::catalog::Catalog;
+use crate::config::Config;
+use crate::output;
+use crate::table::slug;
+use crate::value::author_name;
+use mdbook::book::Chapter;
+use mdbook::BookItem;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Where the listing for a tag is written.
+pub(crate) fn tag_path(config: &Config, tag: &str) -> PathBuf {
+    Path::new(&config.tags_dir).join(format!("{}.md", slug(tag)))
+}
+
+/// A "Tags" chapter listing every tag, with one sub-chapter per tag listing
+/// its chapters. `None` if no chapter has tags.
+pub(crate) fn tag_pages(catalog: &Catalog, config: &Config) -> Option<Chapter> {
+    // tags sorted case-insensitively, each with its chapters in book order
+    let mut tags: BTreeMap<String, (String, Vec<&Path>)> = BTreeMap::new();
+    for entry in &catalog.entries {
+        for tag in entry.list("tags") {
+            let (_, chapters) = tags
+                .entry(tag.to_lowercase())
+                .or_insert_with(|| (tag.clone(), vec![]));
+            chapters.push(&entry.path);
+        }
+    }
+    if tags.is_empty() {
+        return None;
+    }
+
+    let index_path = Path::new(&config.tags_dir).join("index.md");
+    let mut index = format!("# {}\n\n", config.tags_title);
+    if config.tag_cloud {
+        index.push_str(&tag_cloud(&tags, &index_path, config));
+    }
+    let mut pages = vec![];
+    for (tag, chapters) in tags.values() {
+        let path = tag_path(config, tag);
+        if !config.tag_cloud {
+            index.push_str(&format!(
+                "- [{}]({}) ({})\n",
+                markdown_text(tag),
+                relative_link(&index_path, &path),
+                chapters.len()
+            ));
+        }
+
+        let mut content = format!("# {}: {}\n\n", config.tags_title, markdown_text(tag));
+        for chapter in chapters {
+            content.push_str(&format!(
+                "- [{}]({})\n",
+                markdown_text(catalog.title(chapter)),
+                relative_link(&path, chapter)
+            ));
+        }
+        pages.push(generated_chapter(
+            tag,
+            content,
+            path,
+            vec![config.tags_title.clone()],
+        ));
+    }
+
+    let mut chapter = generated_chapter(&config.tags_title, index, index_path, vec![]);
+    chapter.sub_items = pages.into_iter().map(BookItem::Chapter).collect();
+    Some(chapter)
+}
+
+/// Every tag as a link sized by how many chapters use it, from 80% for the
+/// least used to 200% for the most used.
+fn tag_cloud(
+    tags: &BTreeMap<String, (String, Vec<&Path>)>,
+    index_path: &Path,
+    config: &Config,
+) -> String {
+    let counts = tags.values().map(|(_, chapters)| chapters.len());
+    let (min, max) = (counts.clone().min().unwrap_or(1), counts.max().unwrap_or(1));
+
+    let links: Vec<_> = tags
+        .values()
+        .map(|(tag, chapters)| {
+            let size = match max - min {
+                0 => 100,
+                range => 80 + 120 * (chapters.len() - min) / range,
+            };
+            format!(
+                "<a class=\"fm-tag\" href=\"{}\" style=\"font-size: {}%\" title=\"{}\">{}</a>",
+                output::relative_url(index_path, &tag_path(config, tag)),
+                size,
+                chapters.len(),
+                tag
+            )
+        })
+        .collect();
+    format!("<p class=\"fm-tag-cloud\">\n{}\n</p>\n", links.join("\n"))
+}
+
+/// Keys listing a chapter's authors.
+pub(crate) const AUTHOR_KEYS: [&str; 2] = ["author", "authors"];
+
+/// Link to an author's entry on the authors page.
+pub(crate) fn author_url(config: &Config, from: &Path, name: &str) -> String {
+    format!(
+        "{}#{}",
+        output::relative_url(from, Path::new(&config.authors_path)),
+        author_anchor(name)
+    )
+}
+
+fn author_anchor(name: &str) -> String {
+    format!("author-{}", slug(name))
+}
+
+/// An "Authors" chapter listing every author with the chapters they wrote.
+/// `None` if no chapter has an author.
+pub(crate) fn author_page(catalog: &Catalog, config: &Config) -> Option<Chapter> {
+    // authors sorted case-insensitively, each with their chapters in book order
+    let mut authors: BTreeMap<String, (String, Vec<&Path>)> = BTreeMap::new();
+    for entry in &catalog.entries {
+        for key in AUTHOR_KEYS {
+            for author in entry.list(key) {
+                let name = author_name(&author).to_string();
+                let (_, chapters) = authors
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| (name, vec![]));
+                if !chapters.contains(&entry.path.as_path()) {
+                    chapters.push(&entry.path);
+                }
+            }
+        }
+    }
+    if authors.is_empty() {
+        return None;
+    }
+
+    let path = PathBuf::from(&config.authors_path);
+    let mut content = format!(
+        "# {}
+",
+        config.authors_title
+    );
+    for (name, chapters) in authors.values() {
+        content.push_str(&format!(
+            "\n<h2 id=\"{}\">{}</h2>\n\n",
+            author_anchor(name),
+            name
+        ));
+        for chapter in chapters {
+            content.push_str(&format!(
+                "- [{}]({})\n",
+                markdown_text(catalog.title(chapter)),
+                relative_link(&path, chapter)
+            ));
+        }
+    }
+    Some(generated_chapter(
+        &config.authors_title,
+        content,
+        path,
+        vec![],
+    ))
+}
+
+/// The segments of a `category: networking/tls` value.
+fn category_segments(category: &str) -> Vec<String> {
+    category
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Where the listing for a category (given by its segments) is written.
+fn category_path(config: &Config, segments: &[String]) -> PathBuf {
+    let mut path = PathBuf::from(&config.categories_dir);
+    path.extend(segments.iter().map(|segment| slug(segment)));
+    path.join("index.md")
+}
+
+/// A breadcrumb trail from the categories page to a chapter's category.
+pub(crate) fn breadcrumbs(config: &Config, from: &Path, category: &str) -> Option<String> {
+    let segments = category_segments(category);
+    if segments.is_empty() {
+        return None;
+    }
+    let mut crumbs = vec![format!(
+        "<a href=\"{}\">{}</a>",
+        output::relative_url(from, &category_path(config, &[])),
+        config.categories_title
+    )];
+    for i in 0..segments.len() {
+        crumbs.push(format!(
+            "<a href=\"{}\">{}</a>",
+            output::relative_url(from, &category_path(config, &segments[..=i])),
+            segments[i]
+        ));
+    }
+    Some(format!(
+        "<nav class=\"fm-breadcrumbs\">{}</nav>\n\n",
+        crumbs.join(" › ")
+    ))
+}
+
+/// A category and everything filed under it.
+#[derive(Default)]
+struct Category<'c> {
+    name: String,
+    /// Sub-categories by lowercase name.
+    children: BTreeMap<String, Category<'c>>,
+    chapters: Vec<&'c Path>,
+}
+
+/// A "Categories" chapter with a nested page per category, listing its
+/// sub-categories and chapters. `None` if no chapter has a category.
+pub(crate) fn category_pages(catalog: &Catalog, config: &Config) -> Option<Chapter> {
+    let mut root = Category {
+        name: config.categories_title.clone(),
+        ..Category::default()
+    };
+    for entry in &catalog.entries {
+        let Some(category) = entry.value("category") else {
+            continue;
+        };
+        let mut node = &mut root;
+        for segment in category_segments(category) {
+            node = node
+                .children
+                .entry(segment.to_lowercase())
+                .or_insert_with(|| Category {
+                    name: segment,
+                    ..Category::default()
+                });
+        }
+        node.chapters.push(&entry.path);
+    }
+    if root.children.is_empty() {
+        return None;
+    }
+    Some(category_page(&root, &[], catalog, config, &[]))
+}
+
+fn category_page(
+    category: &Category,
+    segments: &[String],
+    catalog: &Catalog,
+    config: &Config,
+    parent_names: &[String],
+) -> Chapter {
+    let path = category_path(config, segments);
+    let mut content = format!("# {}\n\n", markdown_text(&category.name));
+    for child in category.children.values() {
+        let child_segments = [segments, std::slice::from_ref(&child.name)].concat();
+        content.push_str(&format!(
+            "- [{}]({})\n",
+            markdown_text(&child.name),
+            relative_link(&path, &category_path(config, &child_segments))
+        ));
+    }
+    for chapter in &category.chapters {
+        content.push_str(&format!(
+            "- [{}]({})\n",
+            markdown_text(catalog.title(chapter)),
+            relative_link(&path, chapter)
+        ));
+    }
+
+    let names = [parent_names, std::slice::from_ref(&category.name)].concat();
+    let sub_items = category
+        .children
+        .values()
+        .map(|child| {
+            let child_segments = [segments, std::slice::from_ref(&child.name)].concat();
+            BookItem::Chapter(category_page(
+                child,
+                &child_segments,
+                catalog,
+                config,
+                &names,
+            ))
+        })
+        .collect();
+    Chapter {
+        sub_items,
+        ..generated_chapter(&category.name, content, path, parent_names.to_vec())
+    }
+}
+
+/// A chapter with no source file, so it gets no "edit" link.
+pub(crate) fn generated_chapter(
+    name: &str,
+    content: String,
+    path: PathBuf,
+    parent_names: Vec<String>,
+) -> Chapter {
+    Chapter {
+        source_path: None,
+        ..Chapter::new(name, content, path, parent_names)
+    }
+}
+
+/// A markdown link between two source files; the renderer turns `.md` into
+/// `.html`.
+pub(crate) fn relative_link(from: &Path, to: &Path) -> String {
+    let depth = from.components().count().saturating_sub(1);
+    format!(
+        "{}{}",
+        "../".repeat(depth),
+        to.to_string_lossy().replace('\\', "/")
+    )
+}
+
+/// Escape text for a markdown link label.
+pub(crate) fn markdown_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
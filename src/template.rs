@@ -0,0 +1,222 @@
+//! Directives in the chapter body that read the chapter's own frontmatter.
+//!
+//! `{{#if fm.key}} .. {{else}} .. {{/if}}` (and `{{#unless fm.key}}`) keep or
+//! drop a section of the chapter depending on the value of `key`, and
+//! `{{fm.key}}` is replaced with the value itself.
+//!
+//! Code blocks and inline code are left as written, so handlebars examples
+//! show as they are.
+use crate::parse;
+use log::warn;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `{{#if fm.key}}`, `{{#unless fm.key}}`, `{{else}}`, `{{/if}}` and
+/// `{{/unless}}`, and a handlebars `{{#if ..}}` or `{{#unless ..}}` on
+/// anything else (without `key`).
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*(?:(?P<open>#if|#unless)\s+(?:fm\.(?P<key>[\w-]+)|[^}]*)|(?P<else>else)|(?P<close>/if|/unless))\s*\}\}")
+        .expect("valid regex")
+});
+
+/// `{{fm.key}}`, or `\{{fm.key}}` to keep it as written.
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?P<escape>\\)?\{\{\s*fm\.(?P<key>[\w-]+)\s*\}\}").expect("valid regex")
+});
+
+/// Apply the conditional blocks in `content`.
+///
+/// A key is true when it is set to something other than an empty, `false`,
+/// `no`, `off` or `0` value. Unbalanced blocks leave the content untouched.
+///
+/// `{{else}}` and the closing tags only count inside a `fm.` block, and
+/// handlebars blocks inside one are kept as written, tags and all.
+///
+/// Warnings name `origin` (the chapter's path) and the line.
+pub(crate) fn conditionals(
+    content: &str,
+    frontmatter: &[(String, String)],
+    origin: &str,
+) -> String {
+    if !TAG.is_match(content) {
+        return content.to_string();
+    }
+
+    // each open block: whether it's an `if`, its condition, and whether its
+    // `else` was seen
+    let mut blocks: Vec<(bool, bool, bool)> = vec![];
+    // handlebars blocks open inside the innermost of them
+    let mut foreign = 0;
+    let visible = |blocks: &[(bool, bool, bool)]| {
+        blocks
+            .iter()
+            .all(|(is_if, condition, in_else)| (condition == is_if) != *in_else)
+    };
+
+    let code = parse::code_ranges(content);
+    let mut output = String::with_capacity(content.len());
+    let mut pos = 0;
+    for caps in TAG.captures_iter(content) {
+        let whole = caps.get_match();
+        if parse::in_ranges(&code, whole.start()) {
+            continue;
+        }
+        let ours = caps.name("key").is_some();
+        if !ours && caps.name("open").is_some() {
+            if !blocks.is_empty() {
+                foreign += 1;
+            }
+            continue;
+        }
+        if !ours && (blocks.is_empty() || foreign > 0) {
+            if caps.name("close").is_some() && foreign > 0 {
+                foreign -= 1;
+            }
+            continue;
+        }
+
+        if visible(&blocks) {
+            output.push_str(&content[pos..whole.start()]);
+        }
+        pos = whole.end();
+
+        if let (Some(open), Some(key)) = (caps.name("open"), caps.name("key")) {
+            blocks.push((
+                open.as_str() == "#if",
+                is_set(frontmatter, key.as_str()),
+                false,
+            ));
+        } else if caps.name("else").is_some() {
+            match blocks.last_mut() {
+                Some((_, _, in_else)) if !*in_else => *in_else = true,
+                _ => return unbalanced(content, origin, whole.start(), whole.as_str()),
+            }
+        } else {
+            let is_if = caps.name("close").is_some_and(|c| c.as_str() == "/if");
+            match blocks.pop() {
+                Some((open_if, _, _)) if open_if == is_if => (),
+                _ => return unbalanced(content, origin, whole.start(), whole.as_str()),
+            }
+        }
+    }
+    if !blocks.is_empty() {
+        return unbalanced(content, origin, content.len(), "end of chapter");
+    }
+    output.push_str(&content[pos..]);
+    output
+}
+
+/// Replace `{{fm.key}}` with the value of `key`.
+///
+/// `\{{fm.key}}` is kept as the literal `{{fm.key}}`. Unknown keys are left as
+/// written.
+pub(crate) fn substitute(content: &str, frontmatter: &[(String, String)], origin: &str) -> String {
+    if !REFERENCE.is_match(content) {
+        return content.to_string();
+    }
+    let code = parse::code_ranges(content);
+    REFERENCE
+        .replace_all(content, |caps: &regex::Captures| {
+            let whole = caps.get_match();
+            if parse::in_ranges(&code, whole.start()) {
+                return whole.as_str().to_string();
+            }
+            if caps.name("escape").is_some() {
+                return whole.as_str()[1..].to_string();
+            }
+            let key = &caps["key"];
+            match frontmatter.iter().find(|(k, _)| k == key) {
+                Some((_, value)) => value.clone(),
+                None => {
+                    warn!(
+                        "{}:{}: {} refers to a key the chapter doesn't have",
+                        origin,
+                        line_of(content, whole.start()),
+                        whole.as_str()
+                    );
+                    whole.as_str().to_string()
+                }
+            }
+        })
+        .into_owned()
+}
+
+fn is_set(frontmatter: &[(String, String)], key: &str) -> bool {
+    frontmatter
+        .iter()
+        .find(|(k, _)| k == key)
+        .is_some_and(|(_, value)| {
+            !matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "" | "false" | "no" | "off" | "0"
+            )
+        })
+}
+
+fn unbalanced(content: &str, origin: &str, offset: usize, at: &str) -> String {
+    warn!(
+        "{}:{}: unbalanced frontmatter conditional at {:?}, leaving the chapter as written",
+        origin,
+        line_of(content, offset),
+        at
+    );
+    content.to_string()
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frontmatter() -> Vec<(String, String)> {
+        vec![
+            ("owner".to_string(), "docs".to_string()),
+            ("beta".to_string(), "no".to_string()),
+        ]
+    }
+
+    #[test]
+    fn substitutes_outside_code_only() {
+        let content = "Owner {{fm.owner}}, `{{fm.owner}}`\n\n```hbs\n{{fm.owner}}\n```\n";
+        assert_eq!(
+            substitute(content, &frontmatter(), "a.md"),
+            "Owner docs, `{{fm.owner}}`\n\n```hbs\n{{fm.owner}}\n```\n"
+        );
+    }
+
+    #[test]
+    fn conditionals_outside_code_only() {
+        let content = "{{#if fm.beta}}beta{{/if}}\n\n```hbs\n{{#if fm.beta}}x{{/if}}\n```\n";
+        assert_eq!(
+            conditionals(content, &frontmatter(), "a.md"),
+            "\n\n```hbs\n{{#if fm.beta}}x{{/if}}\n```\n"
+        );
+    }
+
+    #[test]
+    fn bare_else_and_close_are_text() {
+        let content = "Write {{else}} and {{/if}}.\n{{#unless fm.beta}}stable{{/unless}}\n";
+        assert_eq!(
+            conditionals(content, &frontmatter(), "a.md"),
+            "Write {{else}} and {{/if}}.\nstable\n"
+        );
+    }
+
+    #[test]
+    fn handlebars_blocks_inside_are_kept() {
+        let content = "{{#if fm.owner}}{{#if user}}a{{else}}b{{/if}}{{else}}none{{/if}}\n";
+        assert_eq!(
+            conditionals(content, &frontmatter(), "a.md"),
+            "{{#if user}}a{{else}}b{{/if}}\n"
+        );
+    }
+
+    #[test]
+    fn unbalanced_is_left_as_written() {
+        let content = "{{#if fm.owner}}a{{else}}b{{else}}c{{/if}}\n";
+        assert_eq!(conditionals(content, &frontmatter(), "a.md"), content);
+    }
+}
@@ -0,0 +1,246 @@
+//! Checks of chapters' frontmatter against the book's rules.
+//!
+//! Problems are collected over the whole book, so a build reports every
+//! chapter at once. They are warnings unless the mode is strict, which fails
+//! the build instead. Each names the chapter's path and, where it's about a
+//! line the chapter wrote, the line number.
+use crate::config::{Config, Mode};
+use crate::date;
+use crate::error::FrontmatterError;
+use crate::parse::Diagnostic;
+use crate::schema::Schema;
+use log::warn;
+use mdbook::errors::Error;
+use regex::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Problems found in the book's chapters.
+#[derive(Debug, Default)]
+pub(crate) struct Report {
+    schema: Option<Schema>,
+    problems: Vec<String>,
+}
+
+impl Report {
+    /// A report checking against the book's `schema`, if it has one.
+    pub(crate) fn new(config: &Config, root: &Path) -> Result<Self, Error> {
+        let schema = match &config.schema {
+            Some(path) => Some(Schema::load(&root.join(path))?),
+            None => None,
+        };
+        // a keyword the schema relies on that isn't checked is a problem
+        // like any other, not a silent pass
+        let problems = match (&schema, &config.schema) {
+            (Some(schema), Some(path)) => schema
+                .unsupported()
+                .iter()
+                .map(|unsupported| format!("{}: {}, so it checks nothing", path, unsupported))
+                .collect(),
+            _ => vec![],
+        };
+        Ok(Self { schema, problems })
+    }
+
+    /// Record what's wrong with how a chapter's frontmatter blocks are
+    /// written, from `parse::diagnostics`.
+    pub(crate) fn check_syntax(&mut self, path: &Path, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            self.problem(path, Some(diagnostic.line), &diagnostic.message);
+        }
+    }
+
+    /// Check a chapter's keys, defaults included.
+    ///
+    /// `lines` are where the chapter wrote its keys.
+    pub(crate) fn check(
+        &mut self,
+        path: &Path,
+        frontmatter: &[(String, String)],
+        lines: &[(String, usize)],
+        config: &Config,
+    ) {
+        let line = |key: &str| lines.iter().find(|(k, _)| k == key).map(|(_, line)| *line);
+        let missing: Vec<_> = config
+            .required_keys
+            .iter()
+            .filter(|key| {
+                !frontmatter
+                    .iter()
+                    .any(|(k, value)| k == *key && !value.is_empty())
+            })
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            self.problem(
+                path,
+                None,
+                &format!("missing required {}", missing.join(", ")),
+            );
+        }
+
+        if config.validate_types {
+            for (key, value) in frontmatter.iter().filter(|(_, value)| !value.is_empty()) {
+                if let Some(expected) = invalid_type(key, value, config) {
+                    self.problem(
+                        path,
+                        line(key),
+                        &format!("{}: {:?} is not {}", key, value, expected),
+                    );
+                }
+            }
+        }
+
+        let violations = self
+            .schema
+            .as_ref()
+            .map(|schema| schema.violations(frontmatter))
+            .unwrap_or_default();
+        for (key, violation) in violations {
+            self.problem(path, key.as_deref().and_then(line), &violation);
+        }
+    }
+
+    /// Check the keys a chapter writes itself against the known keys, when
+    /// unknown keys are denied.
+    pub(crate) fn check_keys(
+        &mut self,
+        path: &Path,
+        frontmatter: &[(String, String)],
+        lines: &[(String, usize)],
+        config: &Config,
+    ) {
+        let schema_denies = self.schema.as_ref().is_some_and(Schema::denies_additional);
+        if !config.deny_unknown_keys && !schema_denies {
+            return;
+        }
+
+        let known: Vec<&str> = config
+            .allowed_keys
+            .iter()
+            .map(String::as_str)
+            .chain(self.schema.iter().flat_map(Schema::properties))
+            .collect();
+        let mut unknown: Vec<&str> = vec![];
+        for (key, _) in frontmatter {
+            if !known.contains(&key.as_str()) && !unknown.contains(&key.as_str()) {
+                unknown.push(key);
+            }
+        }
+        let messages: Vec<(Option<usize>, String)> = unknown
+            .iter()
+            .map(|key| {
+                let line = lines.iter().find(|(k, _)| k == key).map(|(_, line)| *line);
+                let message = match known.iter().find(|k| distance(k, key) <= 2) {
+                    Some(suggestion) => {
+                        format!("unknown key {:?}, did you mean {:?}?", key, suggestion)
+                    }
+                    None => format!("unknown key {:?}", key),
+                };
+                (line, message)
+            })
+            .collect();
+        for (line, message) in messages {
+            self.problem(path, line, &message);
+        }
+    }
+
+    /// Record a problem with a chapter, at a line of it if known.
+    pub(crate) fn problem(&mut self, path: &Path, line: Option<usize>, message: &str) {
+        self.problems.push(match line {
+            Some(line) => format!("{}:{}: {}", path.display(), line, message),
+            None => format!("{}: {}", path.display(), message),
+        });
+    }
+
+    /// How many problems were found.
+    pub(crate) fn len(&self) -> usize {
+        self.problems.len()
+    }
+
+    /// Warn about every problem, or fail with all of them in strict mode.
+    pub(crate) fn finish(self, mode: Mode) -> Result<(), Error> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+        if mode == Mode::Strict {
+            return Err(FrontmatterError::Problems(self.problems).into());
+        }
+        for problem in &self.problems {
+            warn!("{}", problem);
+        }
+        Ok(())
+    }
+}
+
+/// Keys holding dates whatever the configuration.
+const DATE_KEYS: &[&str] = &[
+    "date",
+    "created",
+    "updated",
+    "publish_date",
+    "expires",
+    "review_by",
+];
+
+/// Keys holding a URL.
+const URL_KEYS: &[&str] = &["url", "homepage", "repo", "source"];
+
+/// What a value of a well-known key should have been, if it isn't.
+fn invalid_type(key: &str, value: &str, config: &Config) -> Option<&'static str> {
+    let key = key.to_ascii_lowercase();
+    let ends_with = |suffix: &str| {
+        key == suffix
+            || key.ends_with(&format!("_{}", suffix))
+            || key.ends_with(&format!("-{}", suffix))
+    };
+
+    if (config.is_date_key(&key) || DATE_KEYS.contains(&key.as_str()))
+        && date::parse_date(value).is_none()
+    {
+        Some("a date")
+    } else if ends_with("email") && !is_email(value) {
+        Some("an email address")
+    } else if (URL_KEYS.contains(&key.as_str()) || ends_with("url")) && !is_url(value) {
+        Some("a URL")
+    } else {
+        None
+    }
+}
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s.]+$").expect("valid regex"));
+
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:[^\s]+$").expect("valid regex"));
+
+/// Whether a value looks like a single email address, bare or as
+/// `Name <address>`.
+pub(crate) fn is_email(value: &str) -> bool {
+    let value = value.trim();
+    let address = match (value.rfind('<'), value.strip_suffix('>')) {
+        (Some(start), Some(rest)) => &rest[start + 1..],
+        _ => value,
+    };
+    EMAIL.is_match(address)
+}
+
+/// Whether a value is an absolute URL with a scheme and something after it.
+pub(crate) fn is_url(value: &str) -> bool {
+    URL.is_match(value.trim())
+}
+
+/// Edit distance between two keys, for suggesting the key a typo meant.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
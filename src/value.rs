@@ -0,0 +1,57 @@
+//! Helpers for interpreting frontmatter values.
+
+/// Split a list value into items.
+///
+/// Accepts `[a, "b"]` arrays as well as comma or " and " separated text.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+
+    value
+        .split(',')
+        .flat_map(|part| part.split(" and "))
+        .map(|item| item.trim().trim_matches(|c| c == '"' || c == '\'').trim())
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The name part of an author entry: "Alice (@alice)" becomes "Alice".
+pub(crate) fn author_name(author: &str) -> &str {
+    author.split(" (").next().unwrap_or(author).trim()
+}
+
+/// Whether a flag value like `noindex: true` is set.
+pub(crate) fn is_true(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "true" | "yes" | "on" | "1"
+    )
+}
+
+/// Whether a flag value like `numbered: false` is explicitly unset.
+pub(crate) fn is_false(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "false" | "no" | "off" | "0"
+    )
+}
+
+/// A value from a TOML file as it would be written in frontmatter.
+///
+/// Strings are taken as written, arrays become comma separated lists and
+/// anything else uses its TOML form (`2`, `true`).
+pub(crate) fn from_toml(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        toml::Value::Array(items) => items
+            .into_iter()
+            .map(from_toml)
+            .collect::<Vec<_>>()
+            .join(", "),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,40 @@
+//! Wrap a chapter in an element carrying its frontmatter, for CSS and JS.
+use crate::config::Config;
+use crate::head::escape;
+use crate::table::slug;
+use crate::value::split_list;
+
+/// Wrap `content` in a `<div>` with an `fm-<key>-<value>` class per value of
+/// each `wrapper_keys` key and a `data-<key>` attribute with the values.
+///
+/// Chapters with none of the keys are left as they are.
+pub(crate) fn wrap(content: String, frontmatter: &[(String, String)], config: &Config) -> String {
+    let mut classes = vec![];
+    let mut attributes = String::new();
+    for key in &config.wrapper_keys {
+        let Some((_, value)) = frontmatter.iter().find(|(k, v)| k == key && !v.is_empty()) else {
+            continue;
+        };
+        let items = split_list(value);
+        classes.extend(
+            items
+                .iter()
+                .map(|item| format!("fm-{}-{}", slug(key), slug(item))),
+        );
+        attributes.push_str(&format!(
+            " data-{}=\"{}\"",
+            slug(key),
+            escape(&items.join(", "))
+        ));
+    }
+    if attributes.is_empty() {
+        return content;
+    }
+
+    format!(
+        "<div class=\"{}\"{}>\n\n{}\n\n</div>\n",
+        classes.join(" "),
+        attributes,
+        content.trim_end()
+    )
+}